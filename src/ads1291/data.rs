@@ -0,0 +1,59 @@
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::data::{self, ChannelData, Desync, GpioStatus, LeadOffStatus};
+
+/// Represents a 6-byte data block from the Ads1291
+#[derive(Copy, Clone, PartialEq, Default, Debug)]
+pub struct Ads1291Data {
+    pub data: [u8; 6],
+}
+
+impl Ads1291Data {
+    /// Whether the block starts with the sync nibble of the status word. A block that does not
+    /// was read out of alignment, and its other fields are meaningless.
+    pub fn is_synced(&self) -> bool {
+        data::is_synced(self.data[0])
+    }
+
+    /// Get the Lead-off status for this data block
+    pub fn lead_off_status(&self) -> LeadOffStatus {
+        let status = (self.data[0] << 1) | (self.data[1] >> 7);
+        LeadOffStatus { status }
+    }
+
+    /// Get the GPIO status for this data block
+    pub fn gpio_status(&self) -> GpioStatus {
+        let status = self.data[1] >> 5;
+        GpioStatus { status }
+    }
+
+    /// Get the data from channel 1
+    pub fn channel_1(&self) -> ChannelData {
+        ChannelData(self.data[3], self.data[4], self.data[5])
+    }
+}
+
+impl TryFrom<[u8; 6]> for Ads1291Data {
+    type Error = Desync;
+
+    /// Take a data block that was read from the device, checking its sync nibble
+    fn try_from(data: [u8; 6]) -> core::result::Result<Self, Desync> {
+        if !data::is_synced(data[0]) {
+            return Err(Desync(data[0]));
+        }
+        Ok(Self { data })
+    }
+}
+
+impl fmt::Display for Ads1291Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[\n\tLead off:\t{};\n\tGPIO:\t{};\n\tch1:\t{}\n]",
+            self.lead_off_status(),
+            self.gpio_status(),
+            self.channel_1(),
+        )
+    }
+}
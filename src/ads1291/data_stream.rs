@@ -0,0 +1,24 @@
+use crate::ads1291::data::Ads1291Data;
+use crate::ads1291::Ads1291;
+use crate::data_stream::{DataStream, Streaming};
+use crate::spi::Interface;
+use crate::NoPin;
+
+/// Ads1291 data stream, see `DataStream`
+pub type Ads1291DataStream<DEV, DRDY = NoPin, PinE = <DEV as Interface>::PinError> =
+    DataStream<Ads1291<DEV, PinE>, DRDY>;
+
+impl<DEV, PinE> Streaming for Ads1291<DEV, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    type Interface = DEV;
+    type Frame = Ads1291Data;
+    type Block = [u8; 6];
+
+    #[inline]
+    fn frame(&self, data: [u8; 6]) -> Ads1291Data {
+        Ads1291Data { data }
+    }
+}
@@ -0,0 +1,189 @@
+/// ADS1291-specific data formats
+pub mod data;
+/// ADS1291-specific data stream
+pub mod data_stream;
+
+use core::marker::PhantomData;
+
+use embedded_hal::digital::InputPin;
+
+use crate::data::check_sync;
+use crate::spi::Interface;
+use crate::{
+    command, Ads129xx, Ads129xxError, Command, DeviceId, Model, NoPin, RegisterState, Result,
+};
+
+use data::Ads1291Data;
+use data_stream::Ads1291DataStream;
+
+/// Represents an ADS1291 single-channel ECG front-end module
+///
+/// The ADS1291 shares the register map of the ADS1292, but only channel 1 is available.
+pub struct Ads1291<DEV, PinE = <DEV as Interface>::PinError> {
+    spi: DEV,
+    registers: RegisterState,
+    _pin_error: PhantomData<fn() -> PinE>,
+}
+
+impl<DEV> Ads1291<DEV>
+where
+    DEV: Interface,
+{
+    /// Create a new Ads1291. Sends SDATAC command, as by default it is in continuous data
+    /// reading mode, so that the registers can be accessed.
+    pub fn new(mut spi: DEV) -> Result<Ads1291<DEV>, DEV::Error, DEV::PinError> {
+        command(&mut spi, Command::SDATAC)?;
+        spi.wait(40);
+        Ok(Ads1291 {
+            spi,
+            registers: RegisterState::default(),
+            _pin_error: PhantomData,
+        })
+    }
+
+    /// Wrap an interface that is already in command mode, for a device that reported `id`
+    pub(crate) fn with_id(
+        spi: DEV,
+        id: DeviceId,
+    ) -> Result<Ads1291<DEV>, DEV::Error, DEV::PinError> {
+        let mut ads = Ads1291 {
+            spi,
+            registers: RegisterState::default(),
+            _pin_error: PhantomData,
+        };
+        ads.check_id(id)?;
+        Ok(ads)
+    }
+
+    /// Use `P` for the errors of the pins, instead of the pin error type of the interface.
+    ///
+    /// Errors of the interface and of the nDRDY pin a data stream owns must convert into it.
+    pub fn with_pin_error<P: From<DEV::PinError>>(self) -> Ads1291<DEV, P> {
+        Ads1291 {
+            spi: self.spi,
+            registers: self.registers,
+            _pin_error: PhantomData,
+        }
+    }
+}
+
+impl<DEV, PinE> Ads1291<DEV, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    /// Initialize the Ads1291. Check that it reports the ADS1291 device ID, and return it.
+    pub fn init(&mut self) -> Result<DeviceId, DEV::Error, PinE> {
+        let id = self.read_device_id()?;
+        self.check_id(id)
+    }
+
+    fn check_id(&mut self, id: DeviceId) -> Result<DeviceId, DEV::Error, PinE> {
+        match id.model() {
+            Some(Model::Ads1291) => (),
+            _ => return Err(Ads129xxError::UnexpectedId(id.0)),
+        }
+
+        Ok(id)
+    }
+
+    /// Start conversions with the START command
+    pub fn start(&mut self) -> Result<(), DEV::Error, PinE> {
+        command(&mut self.spi, Command::START)
+    }
+
+    /// Stop conversions with the STOP command
+    pub fn stop(&mut self) -> Result<(), DEV::Error, PinE> {
+        command(&mut self.spi, Command::STOP)
+    }
+
+    /// Send RDATA command and read a single data block from the ADS1291
+    #[inline]
+    pub fn read_data(&mut self) -> Result<Ads1291Data, DEV::Error, PinE> {
+        // Send Read command
+        command(&mut self.spi, Command::RDATA)?;
+        let mut buf = [0u8; 6];
+        // Receive data
+        self.spi
+            .transfer(&mut buf)
+            .map_err(Ads129xxError::convert_pin)?;
+        check_sync(buf[0])?;
+        Ok(Ads1291Data { data: buf })
+    }
+
+    /// Convert this Ads1291 into a Ads1291DataStream
+    pub fn into_data_stream(self) -> Result<Ads1291DataStream<DEV, NoPin, PinE>, DEV::Error, PinE> {
+        Ads1291DataStream::init(self)
+    }
+
+    /// Convert this Ads1291 into a Ads1291DataStream that reads samples when nDRDY signals them
+    pub fn into_data_stream_with_drdy<DRDY>(
+        self,
+        drdy: DRDY,
+    ) -> Result<Ads1291DataStream<DEV, DRDY, PinE>, DEV::Error, PinE>
+    where
+        DRDY: InputPin,
+        PinE: From<DRDY::Error>,
+    {
+        Ads1291DataStream::with_drdy(self, drdy)
+    }
+}
+
+impl<DEV, PinE> Ads129xx<DEV> for Ads1291<DEV, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    type PinError = PinE;
+
+    fn spi_device(&mut self) -> &mut DEV {
+        &mut self.spi
+    }
+
+    fn into_spi_device(self) -> DEV {
+        self.spi
+    }
+
+    fn register_state(&self) -> &RegisterState {
+        &self.registers
+    }
+
+    fn register_state_mut(&mut self) -> &mut RegisterState {
+        &mut self.registers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{FakeAds, FakeState};
+    use crate::Register;
+
+    #[test]
+    fn ads1291_reads_six_byte_blocks() {
+        let state = FakeState::new();
+        let mut ads = Ads1291::new(FakeAds(&state)).unwrap();
+        match ads.init() {
+            Err(Ads129xxError::UnexpectedId(0x53)) => (),
+            _ => panic!("expected UnexpectedId"),
+        }
+        state.regs.borrow_mut()[Register::ID] = 0x52;
+        assert_eq!(ads.init().unwrap().model(), Some(Model::Ads1291));
+
+        state.status.set(0xC1);
+        state.sample.set(-123_456);
+        let data = ads.read_data().unwrap();
+        assert!(data.lead_off_status().in1n_off());
+        assert_eq!(i32::from(data.channel_1()), -123_456);
+
+        let mut stream = ads.into_data_stream().unwrap();
+        state.transactions();
+        assert_eq!(stream.read().unwrap(), data);
+        assert_eq!(state.transactions(), 1);
+        state.status.set(0x00);
+        match stream.next() {
+            Some(Err(Ads129xxError::FrameDesync(0x00))) => (),
+            _ => panic!("expected FrameDesync"),
+        }
+    }
+}
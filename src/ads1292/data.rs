@@ -1,6 +1,8 @@
+use core::convert::TryFrom;
 use core::fmt;
 
-use crate::data::{ChannelData, GpioStatus, LeadOffStatus};
+use crate::data::{self, ChannelData, Desync, GpioStatus, LeadOffStatus};
+use crate::Channel;
 
 /// Represents a 9-byte data block from the Ads1292
 #[derive(Copy, Clone, PartialEq, Default, Debug)]
@@ -9,6 +11,12 @@ pub struct Ads1292Data {
 }
 
 impl Ads1292Data {
+    /// Whether the block starts with the sync nibble of the status word. A block that does not
+    /// was read out of alignment, and its other fields are meaningless.
+    pub fn is_synced(&self) -> bool {
+        data::is_synced(self.data[0])
+    }
+
     /// Get the Lead-off status for this data block
     pub fn lead_off_status(&self) -> LeadOffStatus {
         let status = (self.data[0] << 1) | (self.data[1] >> 7);
@@ -30,11 +38,25 @@ impl Ads1292Data {
     pub fn channel_2(&self) -> ChannelData {
         ChannelData(self.data[6], self.data[7], self.data[8])
     }
+
+    /// Get the data from a channel
+    pub fn channel(&self, ch: Channel) -> ChannelData {
+        match ch {
+            Channel::Ch1 => self.channel_1(),
+            Channel::Ch2 => self.channel_2(),
+        }
+    }
 }
 
-impl From<[u8; 9]> for Ads1292Data {
-    fn from(data: [u8; 9]) -> Self {
-        Self { data }
+impl TryFrom<[u8; 9]> for Ads1292Data {
+    type Error = Desync;
+
+    /// Take a data block that was read from the device, checking its sync nibble
+    fn try_from(data: [u8; 9]) -> core::result::Result<Self, Desync> {
+        if !data::is_synced(data[0]) {
+            return Err(Desync(data[0]));
+        }
+        Ok(Self { data })
     }
 }
 
@@ -3,91 +3,659 @@ pub mod data;
 /// ADS1292-specific data stream
 pub mod data_stream;
 
-use crate::spi::SpiDevice;
-use crate::{Ads129xx, Ads129xxError, Command, Register, Result};
+use core::marker::PhantomData;
+
+use embedded_hal::digital::{InputPin, OutputPin};
+
+use crate::data::{check_sync, div_round, temperature_millicelsius, Scaling, Supply};
+use crate::mode::PowerDown;
+use crate::spi::Interface;
+use crate::{
+    command, recover_from_reset, Ads129xx, Ads129xxError, Channel, ChannelSettings, Command, Conf1,
+    ControlPin, DataReady, DeviceId, GainSetting, InputSelection, Model, NoPin, RegisterState,
+    Result,
+};
 
 use data::Ads1292Data;
 use data_stream::Ads1292DataStream;
-use embedded_hal::blocking::spi as bspi;
-use embedded_hal::digital::v2::OutputPin;
-use embedded_hal::timer::CountDown;
 
-/// Represents an ADS1292 ECG front-end module
-pub struct Ads1292<SPI, NCS, TIM> {
-    spi: SpiDevice<SPI, NCS, TIM>,
+/// Width of the pulse on PWDN/RESET, in device clock periods.
+///
+/// tRST is at least 1 tCLK; holding the pin low for 2^10 tCLK or more powers the device down
+/// instead.
+const RESET_PULSE_TCLK: u16 = 2;
+/// Device clock periods to wait after releasing PWDN/RESET before resetting the device: tPOR
+/// of 2^12 modulator clock periods, which last 4 tCLK with CLK_DIV at its default of 0
+const POWER_UP_TCLK: u16 = 1 << 14;
+/// Conversions to discard after switching the input of a channel, while the digital filter
+/// settles
+const SETTLING_SAMPLES: u16 = 4;
+/// MVDD on channel 1 measures half of the analog supply
+const AVDD_DIVIDER: i64 = 2;
+/// MVDD on channel 2 measures a quarter of the digital supply
+const DVDD_DIVIDER: i64 = 4;
+
+/// Represents an ADS1292 ECG front-end module in command mode, see `mode` for the others
+///
+/// The driver can optionally own the START and PWDN/RESET pins, see `with_start_pin` and
+/// `with_reset_pin`. Without them, conversions are controlled with SPI commands. Errors of these
+/// pins and of the chip select are reported as `Ads129xxError::Pin`, with the pin error type
+/// of the interface unless another one is chosen with `with_pin_error`.
+pub struct Ads1292<DEV, START = NoPin, RESET = NoPin, PinE = <DEV as Interface>::PinError> {
+    spi: DEV,
+    registers: RegisterState,
+    start: START,
+    reset: RESET,
+    /// Whether `start` was called more recently than `stop`
+    running: bool,
+    _pin_error: PhantomData<fn() -> PinE>,
 }
 
-impl<SPI, NCS, TIM, E> Ads1292<SPI, NCS, TIM>
+impl<DEV> Ads1292<DEV>
 where
-    SPI: bspi::Transfer<u8, Error = E> + bspi::Write<u8, Error = E>,
-    NCS: OutputPin<Error = core::convert::Infallible>,
-    TIM: CountDown,
+    DEV: Interface,
 {
-    /// Create a new Ads1292.
-    pub fn new(spi: SpiDevice<SPI, NCS, TIM>) -> Ads1292<SPI, NCS, TIM> {
-        Ads1292 { spi }
-    }
-
-    /// Initialize the Ads1292. Sends SDATAC command, as by default it is in continuous data
-    /// reading mode. Check that it reports a valid device ID.
-    pub fn init(&mut self) -> Result<(), E> {
-        // We start in DATAC, thus need to stop it.
-        self.cmd(Command::SDATAC)?;
-        self.spi.wait(40);
-
-        let id = self.read_register(Register::ID)?;
-        if id & 0x10 != 0x10 {
-            // Bit 4 must be high in ID.
-            return Err(Ads129xxError::BootFailure);
+    /// Create a new Ads1292. Sends SDATAC command, as by default it is in continuous data
+    /// reading mode, so that the registers can be accessed.
+    pub fn new(mut spi: DEV) -> Result<Ads1292<DEV>, DEV::Error, DEV::PinError> {
+        command(&mut spi, Command::SDATAC)?;
+        spi.wait(40);
+        Ok(Ads1292 {
+            spi,
+            registers: RegisterState::default(),
+            start: NoPin,
+            reset: NoPin,
+            running: false,
+            _pin_error: PhantomData,
+        })
+    }
+
+    /// Wrap an interface that is already in command mode, for a device that reported `id`
+    pub(crate) fn with_id(
+        spi: DEV,
+        id: DeviceId,
+    ) -> Result<Ads1292<DEV>, DEV::Error, DEV::PinError> {
+        let mut ads = Ads1292 {
+            spi,
+            registers: RegisterState::default(),
+            start: NoPin,
+            reset: NoPin,
+            running: false,
+            _pin_error: PhantomData,
+        };
+        ads.check_id(id)?;
+        Ok(ads)
+    }
+
+    /// Use `P` for the errors of the pins, instead of the pin error type of the interface.
+    ///
+    /// Errors of the interface, of the control pins and of the nDRDY pin a data stream owns
+    /// must convert into it; with an interface that cannot fail, like `spi::SpiInterface`, that
+    /// takes a `From<Infallible>` implementation. Pick it before handing over control pins, as
+    /// `with_start_pin` and `with_reset_pin` require their errors to convert into it.
+    pub fn with_pin_error<P: From<DEV::PinError>>(self) -> Ads1292<DEV, NoPin, NoPin, P> {
+        Ads1292 {
+            spi: self.spi,
+            registers: self.registers,
+            start: self.start,
+            reset: self.reset,
+            running: self.running,
+            _pin_error: PhantomData,
         }
+    }
+}
 
-        Ok(())
+impl<DEV, START, RESET, PinE> Ads1292<DEV, START, RESET, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    /// Let the driver control conversions through the START pin.
+    ///
+    /// The pin is driven low, as the START and STOP commands are not to be used while it is
+    /// connected. Several devices can share one START line, so that starting one starts all of
+    /// them in sync. Pin errors must convert into the pin error type of the driver, see
+    /// `with_pin_error`.
+    pub fn with_start_pin<P>(
+        self,
+        mut pin: P,
+    ) -> Result<Ads1292<DEV, P, RESET, PinE>, DEV::Error, PinE>
+    where
+        P: OutputPin,
+        PinE: From<P::Error>,
+    {
+        pin.set_low().map_err(|e| Ads129xxError::Pin(e.into()))?;
+        Ok(Ads1292 {
+            spi: self.spi,
+            registers: self.registers,
+            start: pin,
+            reset: self.reset,
+            running: false,
+            _pin_error: PhantomData,
+        })
+    }
+
+    /// Let the driver reset the device through the PWDN/RESET pin. The pin is driven high.
+    ///
+    /// Like for `with_start_pin`, pin errors must convert into the pin error type of the
+    /// driver.
+    pub fn with_reset_pin<P>(
+        self,
+        mut pin: P,
+    ) -> Result<Ads1292<DEV, START, P, PinE>, DEV::Error, PinE>
+    where
+        P: OutputPin,
+        PinE: From<P::Error>,
+    {
+        pin.set_high().map_err(|e| Ads129xxError::Pin(e.into()))?;
+        Ok(Ads1292 {
+            spi: self.spi,
+            registers: self.registers,
+            start: self.start,
+            reset: pin,
+            running: self.running,
+            _pin_error: PhantomData,
+        })
+    }
+
+    /// Consume self and release the SPI interface and the control pins
+    pub fn release(self) -> (DEV, START, RESET) {
+        (self.spi, self.start, self.reset)
+    }
+
+    /// Initialize the Ads1292. Check that it reports an ADS1292 or ADS1292R device ID, and
+    /// return it.
+    pub fn init(&mut self) -> Result<DeviceId, DEV::Error, PinE> {
+        let id = self.read_device_id()?;
+        self.check_id(id)
+    }
+
+    fn check_id(&mut self, id: DeviceId) -> Result<DeviceId, DEV::Error, PinE> {
+        match id.model() {
+            Some(Model::Ads1292) | Some(Model::Ads1292R) => (),
+            _ => return Err(Ads129xxError::UnexpectedId(id.0)),
+        }
+
+        Ok(id)
     }
 
     /// Send RDATA command and read a single data block from the ADS1292
     #[inline]
-    pub fn read_data(&mut self) -> Result<Ads1292Data, E> {
+    pub fn read_data(&mut self) -> Result<Ads1292Data, DEV::Error, PinE> {
         // Send Read command
-        self.cmd(Command::RDATA)?;
+        command(&mut self.spi, Command::RDATA)?;
         let mut buf = [0u8; 9];
         // Receive data
-        self.spi.transfer(&mut buf)?;
-        Ok(buf.into())
+        self.spi
+            .transfer(&mut buf)
+            .map_err(Ads129xxError::convert_pin)?;
+        check_sync(buf[0])?;
+        Ok(Ads1292Data { data: buf })
     }
 
-    /// Read a single data block without sending the RDATA command first
-    /// To be used in RDATAC mode.
-    /// WARNING: This function retrieves ecg data more power efficiently by avoiding the delays
-    /// that are usually necessary when communicating with the ADS1292 device. Use a delay of at
-    /// least 50 microseconds between retrieving samples and between retrieving a sample and
-    /// sending any other command, register read or register write.
-    pub fn read(&mut self) -> Result<Ads1292Data, E> {
-        let mut buf = [0u8; 9];
+    fn read_channel(&mut self, ch: Channel) -> Result<ChannelSettings, DEV::Error, PinE> {
+        match ch {
+            Channel::Ch1 => self.read_chan1(),
+            Channel::Ch2 => self.read_chan2(),
+        }
+    }
 
-        // Receive data
-        unsafe {
-            self.spi.unsafe_transfer(&mut buf)?;
+    fn write_channel(
+        &mut self,
+        ch: Channel,
+        settings: &ChannelSettings,
+    ) -> Result<(), DEV::Error, PinE> {
+        match ch {
+            Channel::Ch1 => self.write_chan1(settings),
+            Channel::Ch2 => self.write_chan2(settings),
         }
-        Ok(buf.into())
+    }
+
+    /// One sample period at the data rate of `conf1`, in device clock periods.
+    ///
+    /// The data rates hold for a 512 kHz clock with CLK_DIV at 0, or a 2.048 MHz clock with
+    /// CLK_DIV at 1.
+    fn sample_period_tclk(&mut self, conf1: Conf1) -> Result<u16, DEV::Error, PinE> {
+        // Assume the slowest rate for the reserved setting
+        let sps = conf1.oversampling().sps().unwrap_or(125);
+        let fclk = if self.read_lead_off_status()?.clk_div() == 1 {
+            2_048_000
+        } else {
+            512_000
+        };
+        Ok((fclk / u32::from(sps)) as u16)
     }
 
     /// Convert this Ads1292 into a Ads1292DataStream
-    pub fn into_data_stream(self) -> Result<Ads1292DataStream<SPI, NCS, TIM, E>, E> {
+    pub fn into_data_stream(
+        self,
+    ) -> Result<Ads1292DataStream<DEV, NoPin, START, RESET, PinE>, DEV::Error, PinE> {
         Ads1292DataStream::init(self)
     }
+
+    /// Convert this Ads1292 into a Ads1292DataStream that reads samples when nDRDY signals them
+    pub fn into_data_stream_with_drdy<DRDY>(
+        self,
+        drdy: DRDY,
+    ) -> Result<Ads1292DataStream<DEV, DRDY, START, RESET, PinE>, DEV::Error, PinE>
+    where
+        DRDY: InputPin,
+        PinE: From<DRDY::Error>,
+    {
+        Ads1292DataStream::with_drdy(self, drdy)
+    }
 }
 
-impl<SPI, NCS, TIM, E> Ads129xx<SPI, NCS, TIM, E> for Ads1292<SPI, NCS, TIM>
+impl<DEV, START, RESET, PinE> Ads1292<DEV, START, RESET, PinE>
 where
-    SPI: bspi::Transfer<u8, Error = E> + bspi::Write<u8, Error = E>,
-    NCS: OutputPin<Error = core::convert::Infallible>,
-    TIM: CountDown,
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+    START: ControlPin<PinE>,
+    RESET: ControlPin<PinE>,
 {
-    fn spi_device(&mut self) -> &mut SpiDevice<SPI, NCS, TIM> {
+    /// Start conversions, by driving the START pin high if the driver owns it, or by sending
+    /// the START command otherwise.
+    pub fn start(&mut self) -> Result<(), DEV::Error, PinE> {
+        match self.start.set_level(true) {
+            Some(res) => res.map_err(Ads129xxError::Pin)?,
+            None => command(&mut self.spi, Command::START)?,
+        }
+        self.running = true;
+        Ok(())
+    }
+
+    /// Stop conversions, by driving the START pin low if the driver owns it, or by sending the
+    /// STOP command otherwise.
+    pub fn stop(&mut self) -> Result<(), DEV::Error, PinE> {
+        match self.start.set_level(false) {
+            Some(res) => res.map_err(Ads129xxError::Pin)?,
+            None => command(&mut self.spi, Command::STOP)?,
+        }
+        self.running = false;
+        Ok(())
+    }
+
+    /// Reset the device by pulsing the PWDN/RESET pin, or with the RESET command if the driver
+    /// does not own the pin, and wait until it accepts commands again.
+    ///
+    /// Conversions are stopped: the START pin is driven low if the driver owns it. The device
+    /// comes out of reset in RDATAC mode, so this sends SDATAC to return to command mode. All
+    /// registers have their default values afterwards; if the register cache is enabled, it is
+    /// reloaded.
+    pub fn hardware_reset(&mut self) -> Result<(), DEV::Error, PinE> {
+        if let Some(res) = self.start.set_level(false) {
+            res.map_err(Ads129xxError::Pin)?;
+        }
+        match self.reset.set_level(false) {
+            Some(res) => {
+                res.map_err(Ads129xxError::Pin)?;
+                self.spi.wait_tclk(RESET_PULSE_TCLK);
+                if let Some(res) = self.reset.set_level(true) {
+                    res.map_err(Ads129xxError::Pin)?;
+                }
+            }
+            None => command(&mut self.spi, Command::RESET)?,
+        }
+        recover_from_reset(self)
+    }
+
+    /// Measure the die temperature with the internal sensor, in millidegrees Celsius.
+    ///
+    /// Switches channel `ch` to the temperature sensor at a gain of 1, and starts continuous
+    /// conversions, also if CONFIG1 selects single-shot mode. The first samples are discarded
+    /// while the filter settles, then `samples` readings (at least one) are averaged.
+    ///
+    /// Each sample is read once `drdy`, the nDRDY pin, signals it. Pass `NoPin` if nDRDY is
+    /// not connected; samples are then paced by the data rate in CONFIG1. Afterwards, CONFIG1
+    /// and the channel settings are restored and conversions are left running or stopped as
+    /// they were, also if the measurement failed.
+    ///
+    /// Fails with `UnknownScaling` if the reference comes from VREFP.
+    pub fn measure_temperature<DRDY>(
+        &mut self,
+        ch: Channel,
+        samples: u16,
+        mut drdy: DRDY,
+    ) -> Result<i32, DEV::Error, PinE>
+    where
+        DRDY: DataReady<PinE>,
+    {
+        let nv = self.measure_input(
+            ch,
+            InputSelection::TemperatureSensor,
+            GainSetting::G1,
+            samples,
+            &mut drdy,
+        )?;
+        Ok(temperature_millicelsius(nv))
+    }
+
+    /// Measure the analog and digital supply voltages through the MVDD input.
+    ///
+    /// Channel 1 measures (AVDD - AVSS) / 2 and channel 2 measures DVDD / 4, both at a gain of
+    /// 1 so that the PGA does not saturate. Each channel is measured in turn like in
+    /// `measure_temperature`, averaging `samples` readings paced by `drdy`, and the settings
+    /// are restored afterwards.
+    ///
+    /// With the 2.42 V reference, (AVDD - AVSS) / 2 is beyond the input range for an analog
+    /// supply above 4.84 V; select the 4.033 V reference with `Conf2::set_vref_4v` to measure
+    /// such supplies. Fails with `InputSaturated` if a sample reads a full-scale code, and with
+    /// `UnknownScaling` if the reference comes from VREFP.
+    pub fn measure_supply<DRDY>(
+        &mut self,
+        samples: u16,
+        mut drdy: DRDY,
+    ) -> Result<Supply, DEV::Error, PinE>
+    where
+        DRDY: DataReady<PinE>,
+    {
+        let avdd_nv = self.measure_input(
+            Channel::Ch1,
+            InputSelection::MVDD,
+            GainSetting::G1,
+            samples,
+            &mut drdy,
+        )?;
+        let dvdd_nv = self.measure_input(
+            Channel::Ch2,
+            InputSelection::MVDD,
+            GainSetting::G1,
+            samples,
+            &mut drdy,
+        )?;
+        let uv = |nv: i64, divider: i64| div_round(nv.max(0) * divider, 1000) as u32;
+        Ok(Supply {
+            avdd_uv: uv(avdd_nv, AVDD_DIVIDER),
+            dvdd_uv: uv(dvdd_nv, DVDD_DIVIDER),
+        })
+    }
+
+    /// Average the voltage on the input of channel `ch` selected by `input`, in nanovolts, and
+    /// restore CONFIG1, the channel settings and the conversion state afterwards.
+    fn measure_input<DRDY>(
+        &mut self,
+        ch: Channel,
+        input: InputSelection,
+        gain: GainSetting,
+        samples: u16,
+        drdy: &mut DRDY,
+    ) -> Result<i64, DEV::Error, PinE>
+    where
+        DRDY: DataReady<PinE>,
+    {
+        let conf1 = self.read_conf1()?;
+        let saved = self.read_channel(ch)?;
+        let running = self.running;
+        let res = self.average_input(ch, conf1, saved, input, gain, samples, drdy);
+        let restored = self
+            .stop()
+            .and_then(|()| self.write_conf1(&conf1))
+            .and_then(|()| self.write_channel(ch, &saved))
+            .and_then(|()| if running { self.start() } else { Ok(()) });
+        let nv = res?;
+        restored?;
+        Ok(nv)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn average_input<DRDY>(
+        &mut self,
+        ch: Channel,
+        mut conf1: Conf1,
+        mut settings: ChannelSettings,
+        input: InputSelection,
+        gain: GainSetting,
+        samples: u16,
+        drdy: &mut DRDY,
+    ) -> Result<i64, DEV::Error, PinE>
+    where
+        DRDY: DataReady<PinE>,
+    {
+        settings.set_pd(false);
+        settings.set_gain(gain);
+        settings.set_mux(input);
+        let scaling = Scaling::from_registers(settings, self.read_conf2()?)
+            .ok_or(Ads129xxError::UnknownScaling)?;
+        self.stop()?;
+        if conf1.single_shot() {
+            conf1.set_single_shot(false);
+            self.write_conf1(&conf1)?;
+        }
+        self.write_channel(ch, &settings)?;
+        let period = self.sample_period_tclk(conf1)?;
+
+        self.start()?;
+        let samples = samples.max(1);
+        let mut sum = 0;
+        for i in 0..SETTLING_SAMPLES + samples {
+            self.wait_for_sample(drdy, period)?;
+            let data = self.read_data()?.channel(ch);
+            if i >= SETTLING_SAMPLES {
+                if data.is_saturated() {
+                    return Err(Ads129xxError::InputSaturated);
+                }
+                sum += scaling.nanovolts(data);
+            }
+        }
+        Ok(div_round(sum, i64::from(samples)))
+    }
+
+    /// Wait until nDRDY signals a new sample, or for one sample period if it is not connected
+    fn wait_for_sample<DRDY>(
+        &mut self,
+        drdy: &mut DRDY,
+        period_tclk: u16,
+    ) -> Result<(), DEV::Error, PinE>
+    where
+        DRDY: DataReady<PinE>,
+    {
+        loop {
+            match drdy.is_ready() {
+                Some(Ok(true)) => return Ok(()),
+                Some(Ok(false)) => (),
+                Some(Err(e)) => return Err(Ads129xxError::Pin(e)),
+                None => {
+                    self.spi.wait_tclk(period_tclk);
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl<DEV, START, RESET, PinE> Ads1292<DEV, START, RESET, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+    START: ControlPin<PinE>,
+    RESET: OutputPin,
+    PinE: From<RESET::Error>,
+{
+    /// Power the device down by holding the PWDN/RESET pin low.
+    ///
+    /// This needs the driver to own the pin; without it, use `Ads129xx::standby`.
+    pub fn power_down(mut self) -> Result<PowerDown<Self>, DEV::Error, PinE> {
+        self.reset
+            .set_low()
+            .map_err(|e| Ads129xxError::Pin(e.into()))?;
+        Ok(PowerDown { ads: self })
+    }
+}
+
+impl<DEV, START, RESET, PinE> PowerDown<Ads1292<DEV, START, RESET, PinE>>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+    START: ControlPin<PinE>,
+    RESET: OutputPin,
+    PinE: From<RESET::Error>,
+{
+    /// Release the PWDN/RESET pin, wait for the oscillator and reference to start up, and
+    /// reset the device with `hardware_reset`, which returns it to command mode.
+    ///
+    /// The registers have their default values afterwards, like after `hardware_reset`.
+    pub fn power_up(self) -> Result<Ads1292<DEV, START, RESET, PinE>, DEV::Error, PinE> {
+        let mut ads = self.ads;
+        ads.reset
+            .set_high()
+            .map_err(|e| Ads129xxError::Pin(e.into()))?;
+        ads.spi.wait_tclk(POWER_UP_TCLK);
+        ads.hardware_reset()?;
+        Ok(ads)
+    }
+}
+
+impl<DEV, START, RESET, PinE> Ads129xx<DEV> for Ads1292<DEV, START, RESET, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    type PinError = PinE;
+
+    fn spi_device(&mut self) -> &mut DEV {
         &mut self.spi
     }
 
-    fn into_spi_device(self) -> SpiDevice<SPI, NCS, TIM> {
+    fn into_spi_device(self) -> DEV {
         self.spi
     }
+
+    fn register_state(&self) -> &RegisterState {
+        &self.registers
+    }
+
+    fn register_state_mut(&mut self) -> &mut RegisterState {
+        &mut self.registers
+    }
+
+    /// Conversions started with the START command stop. If the driver owns the START pin, use
+    /// `hardware_reset`, which also drives it low.
+    fn registers_reset(&mut self) {
+        self.running = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{fake_ads1292, BrokenPin, FakeDrdy, FakePin, FakeState, PinFault};
+    use crate::Register;
+    use core::cell::Cell;
+
+    #[test]
+    fn control_pins() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state).with_pin_error::<PinFault>();
+        ads.start().unwrap();
+        assert_eq!(state.transactions(), 1);
+
+        let (start_high, start_pulses) = (Cell::new(true), Cell::new(0));
+        let (reset_high, reset_pulses) = (Cell::new(false), Cell::new(0));
+        let mut ads = ads
+            .with_start_pin(FakePin {
+                high: &start_high,
+                pulses: &start_pulses,
+            })
+            .unwrap()
+            .with_reset_pin(FakePin {
+                high: &reset_high,
+                pulses: &reset_pulses,
+            })
+            .unwrap();
+        assert!(!start_high.get());
+        assert!(reset_high.get());
+
+        ads.start().unwrap();
+        assert!(start_high.get());
+        ads.stop().unwrap();
+        assert!(!start_high.get());
+
+        ads.hardware_reset().unwrap();
+        assert_eq!(reset_pulses.get(), 1);
+        assert!(reset_high.get());
+        // Only SDATAC, as the device comes out of reset in RDATAC mode
+        assert_eq!(state.transactions(), 1);
+
+        // Commands that the driver methods model are refused
+        match ads.cmd(Command::RDATAC) {
+            Err(Ads129xxError::RestrictedCommand(Command::RDATAC)) => (),
+            _ => panic!("expected RestrictedCommand"),
+        }
+        assert_eq!(state.transactions(), 0);
+
+        // Pin errors are reported, converted into the pin error type of the driver
+        match ads.with_start_pin(BrokenPin) {
+            Err(Ads129xxError::Pin(PinFault)) => (),
+            _ => panic!("expected a pin error"),
+        }
+    }
+
+    #[test]
+    fn measure_temperature() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state);
+        let chan2 = ChannelSettings(0xE0);
+        ads.write_chan2(&chan2).unwrap();
+        match ads.measure_temperature(Channel::Ch2, 4, NoPin) {
+            Err(Ads129xxError::UnknownScaling) => (),
+            _ => panic!("expected UnknownScaling"),
+        }
+        assert_eq!(state.regs.borrow()[Register::CH2SET], 0xE0);
+
+        // 150.2 mV with the 2.42 V reference, 10 degrees above 25
+        ads.modify_conf2(|conf| conf.set_pdb_refbuf(true)).unwrap();
+        state.sample.set(520_648);
+        assert_eq!(
+            ads.measure_temperature(Channel::Ch2, 4, NoPin).unwrap(),
+            35_000
+        );
+        assert_eq!(ads.read_chan2().unwrap(), chan2);
+
+        // Single-shot mode is left for the measurement, and running conversions are resumed
+        let (start_high, start_pulses) = (Cell::new(false), Cell::new(0));
+        let mut ads = ads
+            .with_start_pin(FakePin {
+                high: &start_high,
+                pulses: &start_pulses,
+            })
+            .unwrap();
+        ads.write_register(Register::CONFIG1, 0x82).unwrap();
+        ads.start().unwrap();
+        let ready = Cell::new(false);
+        assert_eq!(
+            ads.measure_temperature(Channel::Ch2, 1, FakeDrdy(&ready))
+                .unwrap(),
+            35_000
+        );
+        assert_eq!(state.regs.borrow()[Register::CONFIG1], 0x82);
+        assert!(start_high.get());
+    }
+
+    #[test]
+    fn measure_supply() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state);
+        ads.modify_conf2(|conf| conf.set_pdb_refbuf(true)).unwrap();
+        let chan1 = ads.read_chan1().unwrap();
+
+        // 1.65 V and one LSB on both channels
+        state.sample.set(5_719_508);
+        let supply = ads.measure_supply(4, NoPin).unwrap();
+        assert_eq!(supply.avdd_uv, 3_300_001);
+        assert_eq!(supply.dvdd_uv, 6_600_003);
+        assert!(!supply.is_low(&Supply::MIN));
+        assert_eq!(ads.read_chan1().unwrap(), chan1);
+
+        state.sample.set(1_000_000);
+        let supply = ads.measure_supply(1, NoPin).unwrap();
+        assert!(supply.is_low(&Supply::MIN));
+
+        // A supply above twice the reference clips at full scale
+        state.sample.set(0x7F_FFFF);
+        match ads.measure_supply(1, NoPin) {
+            Err(Ads129xxError::InputSaturated) => (),
+            _ => panic!("expected InputSaturated"),
+        }
+        assert_eq!(ads.read_chan1().unwrap(), chan1);
+    }
 }
@@ -0,0 +1,73 @@
+use core::fmt;
+
+use crate::data::{self, ChannelData, GpioStatus, LeadOffStatus};
+
+/// Represents a 9-byte data block from the Ads1292R
+///
+/// When the respiration demodulator is enabled (`RespConf1::resp_demod_en1`), channel 1 carries
+/// the demodulated respiration signal instead of an ECG lead.
+#[derive(Copy, Clone, PartialEq, Default, Debug)]
+pub struct Ads1292RData {
+    pub data: [u8; 9],
+    /// Whether channel 1 carried the respiration signal when this block was read
+    pub respiration: bool,
+}
+
+impl Ads1292RData {
+    /// Create a data block, indicating whether channel 1 carries the respiration signal
+    pub fn new(data: [u8; 9], respiration: bool) -> Self {
+        Self { data, respiration }
+    }
+
+    /// Whether the block starts with the sync nibble of the status word. A block that does not
+    /// was read out of alignment, and its other fields are meaningless.
+    pub fn is_synced(&self) -> bool {
+        data::is_synced(self.data[0])
+    }
+
+    /// Get the Lead-off status for this data block
+    pub fn lead_off_status(&self) -> LeadOffStatus {
+        let status = (self.data[0] << 1) | (self.data[1] >> 7);
+        LeadOffStatus { status }
+    }
+
+    /// Get the GPIO status for this data block
+    pub fn gpio_status(&self) -> GpioStatus {
+        let status = self.data[1] >> 5;
+        GpioStatus { status }
+    }
+
+    /// Get the data from channel 1, regardless of what it carries
+    pub fn channel_1(&self) -> ChannelData {
+        ChannelData(self.data[3], self.data[4], self.data[5])
+    }
+
+    /// Get the data from channel 2
+    pub fn channel_2(&self) -> ChannelData {
+        ChannelData(self.data[6], self.data[7], self.data[8])
+    }
+
+    /// Get the demodulated respiration signal, if channel 1 carries it
+    pub fn respiration(&self) -> Option<ChannelData> {
+        if self.respiration {
+            Some(self.channel_1())
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Ads1292RData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ch1 = if self.respiration { "resp" } else { "ch1" };
+        write!(
+            f,
+            "[\n\tLead off:\t{};\n\tGPIO:\t{};\n\t{}:\t{};\n\tch2:\t{}\n]",
+            self.lead_off_status(),
+            self.gpio_status(),
+            ch1,
+            self.channel_1(),
+            self.channel_2()
+        )
+    }
+}
@@ -0,0 +1,30 @@
+use crate::ads1292r::data::Ads1292RData;
+use crate::ads1292r::Ads1292R;
+use crate::data_stream::{DataStream, Streaming};
+use crate::spi::Interface;
+use crate::{Ads129xx, NoPin, Result};
+
+/// Ads1292R data stream, see `DataStream`
+pub type Ads1292RDataStream<DEV, DRDY = NoPin, PinE = <DEV as Interface>::PinError> =
+    DataStream<Ads1292R<DEV, PinE>, DRDY>;
+
+impl<DEV, PinE> Streaming for Ads1292R<DEV, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    type Interface = DEV;
+    type Frame = Ads1292RData;
+    type Block = [u8; 9];
+
+    /// Refresh the respiration state from RESP1, as registers cannot be read while streaming
+    fn open_stream(&mut self) -> Result<(), DEV::Error, PinE> {
+        self.respiration = self.read_resp_conf1()?.resp_demod_en1();
+        Ok(())
+    }
+
+    #[inline]
+    fn frame(&self, data: [u8; 9]) -> Ads1292RData {
+        Ads1292RData::new(data, self.respiration)
+    }
+}
@@ -0,0 +1,218 @@
+/// ADS1292R-specific data formats
+pub mod data;
+/// ADS1292R-specific data stream
+pub mod data_stream;
+
+use core::marker::PhantomData;
+
+use embedded_hal::digital::InputPin;
+
+use crate::data::check_sync;
+use crate::spi::Interface;
+use crate::{
+    command, Ads129xx, Ads129xxError, Command, DeviceId, Model, NoPin, Register, RegisterState,
+    RespConf1, Result,
+};
+
+use data::Ads1292RData;
+use data_stream::Ads1292RDataStream;
+
+/// Represents an ADS1292R ECG and respiration front-end module
+///
+/// The driver keeps track of whether the respiration demodulator is routed to channel 1, so that
+/// data blocks can tell respiration and ECG samples apart. This state is read from RESP1 in
+/// `init` and `into_data_stream`, and updated by every write to RESP1, be it through
+/// `write_resp_conf1`, `write_register`, `write_registers` or `apply_config`.
+pub struct Ads1292R<DEV, PinE = <DEV as Interface>::PinError> {
+    spi: DEV,
+    respiration: bool,
+    registers: RegisterState,
+    _pin_error: PhantomData<fn() -> PinE>,
+}
+
+impl<DEV> Ads1292R<DEV>
+where
+    DEV: Interface,
+{
+    /// Create a new Ads1292R. Sends SDATAC command, as by default it is in continuous data
+    /// reading mode, so that the registers can be accessed.
+    pub fn new(mut spi: DEV) -> Result<Ads1292R<DEV>, DEV::Error, DEV::PinError> {
+        command(&mut spi, Command::SDATAC)?;
+        spi.wait(40);
+        Ok(Ads1292R {
+            spi,
+            respiration: false,
+            registers: RegisterState::default(),
+            _pin_error: PhantomData,
+        })
+    }
+
+    /// Wrap an interface that is already in command mode, for a device that reported `id`
+    pub(crate) fn with_id(
+        spi: DEV,
+        id: DeviceId,
+    ) -> Result<Ads1292R<DEV>, DEV::Error, DEV::PinError> {
+        let mut ads = Ads1292R {
+            spi,
+            respiration: false,
+            registers: RegisterState::default(),
+            _pin_error: PhantomData,
+        };
+        ads.check_id(id)?;
+        Ok(ads)
+    }
+
+    /// Use `P` for the errors of the pins, instead of the pin error type of the interface.
+    ///
+    /// Errors of the interface and of the nDRDY pin a data stream owns must convert into it.
+    pub fn with_pin_error<P: From<DEV::PinError>>(self) -> Ads1292R<DEV, P> {
+        Ads1292R {
+            spi: self.spi,
+            respiration: self.respiration,
+            registers: self.registers,
+            _pin_error: PhantomData,
+        }
+    }
+}
+
+impl<DEV, PinE> Ads1292R<DEV, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    /// Initialize the Ads1292R. Check that it reports the ADS1292R device ID, and return it.
+    pub fn init(&mut self) -> Result<DeviceId, DEV::Error, PinE> {
+        let id = self.read_device_id()?;
+        self.check_id(id)
+    }
+
+    /// Check the device ID, and read whether respiration is enabled
+    fn check_id(&mut self, id: DeviceId) -> Result<DeviceId, DEV::Error, PinE> {
+        match id.model() {
+            Some(Model::Ads1292R) => (),
+            _ => return Err(Ads129xxError::UnexpectedId(id.0)),
+        }
+
+        self.respiration = self.read_resp_conf1()?.resp_demod_en1();
+
+        Ok(id)
+    }
+
+    /// Whether channel 1 currently carries the demodulated respiration signal
+    pub fn respiration_enabled(&self) -> bool {
+        self.respiration
+    }
+
+    /// Start conversions with the START command
+    pub fn start(&mut self) -> Result<(), DEV::Error, PinE> {
+        command(&mut self.spi, Command::START)
+    }
+
+    /// Stop conversions with the STOP command
+    pub fn stop(&mut self) -> Result<(), DEV::Error, PinE> {
+        command(&mut self.spi, Command::STOP)
+    }
+
+    /// Send RDATA command and read a single data block from the ADS1292R
+    #[inline]
+    pub fn read_data(&mut self) -> Result<Ads1292RData, DEV::Error, PinE> {
+        // Send Read command
+        command(&mut self.spi, Command::RDATA)?;
+        let mut buf = [0u8; 9];
+        // Receive data
+        self.spi
+            .transfer(&mut buf)
+            .map_err(Ads129xxError::convert_pin)?;
+        check_sync(buf[0])?;
+        Ok(Ads1292RData::new(buf, self.respiration))
+    }
+
+    /// Convert this Ads1292R into a Ads1292RDataStream
+    pub fn into_data_stream(
+        self,
+    ) -> Result<Ads1292RDataStream<DEV, NoPin, PinE>, DEV::Error, PinE> {
+        Ads1292RDataStream::init(self)
+    }
+
+    /// Convert this Ads1292R into a Ads1292RDataStream that reads samples when nDRDY signals them
+    pub fn into_data_stream_with_drdy<DRDY>(
+        self,
+        drdy: DRDY,
+    ) -> Result<Ads1292RDataStream<DEV, DRDY, PinE>, DEV::Error, PinE>
+    where
+        DRDY: InputPin,
+        PinE: From<DRDY::Error>,
+    {
+        Ads1292RDataStream::with_drdy(self, drdy)
+    }
+}
+
+impl<DEV, PinE> Ads129xx<DEV> for Ads1292R<DEV, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    type PinError = PinE;
+
+    fn spi_device(&mut self) -> &mut DEV {
+        &mut self.spi
+    }
+
+    fn into_spi_device(self) -> DEV {
+        self.spi
+    }
+
+    fn register_state(&self) -> &RegisterState {
+        &self.registers
+    }
+
+    fn register_state_mut(&mut self) -> &mut RegisterState {
+        &mut self.registers
+    }
+
+    fn registers_reset(&mut self) {
+        self.respiration = false;
+    }
+
+    fn registers_written(&mut self, start: Register, data: &[u8]) {
+        let offset = (Register::RESP1.addr() as usize).checked_sub(start.addr() as usize);
+        if let Some(&value) = offset.and_then(|i| data.get(i)) {
+            self.respiration = RespConf1(value).resp_demod_en1();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ChannelData;
+    use crate::fake::{FakeAds, FakeState};
+
+    #[test]
+    fn ads1292r_tracks_respiration() {
+        let state = FakeState::new();
+        state.sample.set(-42);
+        let mut ads = Ads1292R::new(FakeAds(&state)).unwrap();
+        let data = ads.read_data().unwrap();
+        assert_eq!(data.respiration(), None);
+        assert_eq!(i32::from(data.channel_1()), -42);
+
+        // Every write path updates whether channel 1 carries the respiration signal
+        ads.write_register(Register::RESP1, 0xC2).unwrap();
+        let data = ads.read_data().unwrap();
+        assert_eq!(data.respiration(), Some(ChannelData::from(-42)));
+
+        ads.write_registers(Register::LOFF_STAT, &[0x00, 0x02])
+            .unwrap();
+        assert!(!ads.respiration_enabled());
+
+        let mut config = ads.read_config().unwrap();
+        config.resp_conf1.set_resp_demod_en1(true);
+        ads.apply_config(&config).unwrap();
+        assert!(ads.read_data().unwrap().respiration().is_some());
+
+        ads.modify_resp_conf1(|resp| resp.set_resp_demod_en1(false))
+            .unwrap();
+        assert_eq!(ads.read_data().unwrap().respiration(), None);
+    }
+}
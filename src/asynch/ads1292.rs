@@ -0,0 +1,307 @@
+use embedded_hal_async::digital::Wait;
+
+use crate::ads1292::data::Ads1292Data;
+use crate::asynch::{command, Ads129xx, Interface};
+use crate::data::{self, RESYNC_ATTEMPTS};
+use crate::{Ads129xxError, Command, DeviceId, Model, RegisterMap, RegisterState, Result};
+
+/// Represents an ADS1292 ECG front-end module, driven asynchronously
+pub struct Ads1292<DEV> {
+    spi: DEV,
+    registers: RegisterState,
+}
+
+impl<DEV> Ads1292<DEV>
+where
+    DEV: Interface,
+{
+    /// Create a new Ads1292. Sends SDATAC command, as by default it is in continuous data
+    /// reading mode, so that the registers can be accessed.
+    pub async fn new(mut spi: DEV) -> Result<Ads1292<DEV>, DEV::Error> {
+        command(&mut spi, Command::SDATAC).await?;
+        spi.wait(40).await;
+        Ok(Ads1292 {
+            spi,
+            registers: RegisterState::default(),
+        })
+    }
+
+    /// Initialize the Ads1292. Check that it reports an ADS1292 or ADS1292R device ID, and
+    /// return it.
+    pub async fn init(&mut self) -> Result<DeviceId, DEV::Error> {
+        let id = self.read_device_id().await?;
+        match id.model() {
+            Some(Model::Ads1292) | Some(Model::Ads1292R) => (),
+            _ => return Err(Ads129xxError::UnexpectedId(id.0)),
+        }
+
+        Ok(id)
+    }
+
+    /// Start conversions with the START command
+    pub async fn start(&mut self) -> Result<(), DEV::Error> {
+        command(&mut self.spi, Command::START).await
+    }
+
+    /// Stop conversions with the STOP command
+    pub async fn stop(&mut self) -> Result<(), DEV::Error> {
+        command(&mut self.spi, Command::STOP).await
+    }
+
+    /// Send RDATA command and read a single data block from the ADS1292
+    #[inline]
+    pub async fn read_data(&mut self) -> Result<Ads1292Data, DEV::Error> {
+        // Send Read command
+        command(&mut self.spi, Command::RDATA).await?;
+        let mut buf = [0u8; 9];
+        // Receive data
+        self.spi.transfer(&mut buf).await?;
+        data::check_sync(buf[0])?;
+        Ok(Ads1292Data { data: buf })
+    }
+
+    /// Convert this Ads1292 into a Ads1292DataStream, which waits for samples on `drdy`
+    pub async fn into_data_stream<DRDY>(
+        self,
+        drdy: DRDY,
+    ) -> Result<Ads1292DataStream<DEV, DRDY>, DEV::Error>
+    where
+        DRDY: Wait,
+    {
+        Ads1292DataStream::init(self, drdy).await
+    }
+}
+
+impl<DEV> Ads129xx<DEV> for Ads1292<DEV>
+where
+    DEV: Interface,
+{
+    fn spi_device(&mut self) -> &mut DEV {
+        &mut self.spi
+    }
+
+    fn into_spi_device(self) -> DEV {
+        self.spi
+    }
+
+    fn register_state(&self) -> &RegisterState {
+        &self.registers
+    }
+
+    fn register_state_mut(&mut self) -> &mut RegisterState {
+        &mut self.registers
+    }
+}
+
+/// Async Ads1292 data stream. Used to read data continuously.
+///
+/// Owns the nDRDY pin, and only clocks out a data block after it has signalled a new sample;
+/// errors of the pin are reported as `Ads129xxError::Pin`.
+///
+/// Every data block is checked for the sync nibble of its status word. After an
+/// `Ads129xxError::FrameDesync`, call `resync` to restore frame alignment.
+pub struct Ads1292DataStream<DEV, DRDY>
+where
+    DEV: Interface,
+{
+    ads1292: Ads1292<DEV>,
+    drdy: DRDY,
+}
+
+impl<DEV, DRDY> Ads1292DataStream<DEV, DRDY>
+where
+    DEV: Interface,
+    DRDY: Wait,
+{
+    /// Initialize stream, send RDATAC command
+    pub async fn init(mut ads1292: Ads1292<DEV>, drdy: DRDY) -> Result<Self, DEV::Error> {
+        command(&mut ads1292.spi, Command::RDATAC).await?;
+        Ok(Self { ads1292, drdy })
+    }
+
+    /// Wait for nDRDY to fall, then read the new data block.
+    pub async fn next(&mut self) -> Result<Ads1292Data, DEV::Error, DRDY::Error> {
+        self.drdy
+            .wait_for_falling_edge()
+            .await
+            .map_err(Ads129xxError::Pin)?;
+        let mut buf = [0u8; 9];
+        self.ads1292.spi.transfer(&mut buf).await?;
+        data::check_sync(buf[0])?;
+        Ok(Ads1292Data { data: buf })
+    }
+
+    /// Restore frame alignment after a `FrameDesync` error.
+    ///
+    /// Restarts continuous reading with SDATAC and RDATAC, then reads the next data blocks
+    /// until one starts with the sync nibble again, and returns it. Gives up with `FrameDesync`
+    /// if the first few blocks are all out of alignment.
+    pub async fn resync(&mut self) -> Result<Ads1292Data, DEV::Error, DRDY::Error> {
+        self.ads1292
+            .cmd(Command::SDATAC)
+            .await
+            .map_err(widen_pin_error)?;
+        command(&mut self.ads1292.spi, Command::RDATAC)
+            .await
+            .map_err(widen_pin_error)?;
+        let mut status = 0;
+        for _ in 0..RESYNC_ATTEMPTS {
+            match self.next().await {
+                Err(Ads129xxError::FrameDesync(s)) => status = s,
+                res => return res,
+            }
+        }
+        Err(Ads129xxError::FrameDesync(status))
+    }
+
+    /// The shadow copy of the register map, if the driver's register cache is enabled.
+    pub fn cached_registers(&self) -> Option<&RegisterMap> {
+        self.ads1292.cached_registers()
+    }
+
+    /// Send SDATAC command, then return wrapped ADS1292 and the nDRDY pin
+    pub async fn into_inner(mut self) -> Result<(Ads1292<DEV>, DRDY), DEV::Error> {
+        self.ads1292.cmd(Command::SDATAC).await?;
+        Ok((self.ads1292, self.drdy))
+    }
+}
+
+/// Give an error of the interface, which has no pin errors, the pin error type of nDRDY
+fn widen_pin_error<E, PinE>(e: Ads129xxError<E>) -> Ads129xxError<E, PinE> {
+    use Ads129xxError::*;
+    match e {
+        UnexpectedId(id) => UnexpectedId(id),
+        InvalidRegisterRange => InvalidRegisterRange,
+        ReadOnlyRegister(reg) => ReadOnlyRegister(reg),
+        RegisterMismatch { reg, wrote, read } => RegisterMismatch { reg, wrote, read },
+        SpiError(e) => SpiError(e),
+        Pin(never) => match never {},
+        UnknownScaling => UnknownScaling,
+        FrameDesync(status) => FrameDesync(status),
+        RestrictedCommand(cmd) => RestrictedCommand(cmd),
+        InputSaturated => InputSaturated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChannelSettings, GainSetting, Register};
+    use core::cell::Cell;
+    use core::convert::Infallible;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    const NOOP_VTABLE: RawWakerVTable =
+        RawWakerVTable::new(|_| NOOP_RAW_WAKER, |_| (), |_| (), |_| ());
+    const NOOP_RAW_WAKER: RawWaker = RawWaker::new(core::ptr::null(), &NOOP_VTABLE);
+
+    /// Poll a future that never has to wait to completion
+    fn ready<F: Future>(f: F) -> F::Output {
+        // Safety: the vtable functions do nothing, so any data pointer is fine
+        let waker = unsafe { Waker::from_raw(NOOP_RAW_WAKER) };
+        let mut cx = Context::from_waker(&waker);
+        match pin!(f).poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future was not ready"),
+        }
+    }
+
+    /// Emulates an ADS1292 that streams the sample counter as channel 1
+    struct FakeAds<'a> {
+        regs: RegisterMap,
+        sample: &'a Cell<u8>,
+    }
+
+    impl Interface for FakeAds<'_> {
+        type Error = Infallible;
+
+        async fn transfer(&mut self, words: &mut [u8]) -> core::result::Result<(), Infallible> {
+            if words[0] & 0xE0 == Command::RREG.word() {
+                let start = (words[0] & 0x1F) as usize;
+                let n = words[1] as usize + 1;
+                words[2..2 + n].copy_from_slice(&self.regs.0[start..start + n]);
+            } else if words.len() == 9 {
+                words[0] = 0xC0;
+                words[5] = self.sample.get();
+            }
+            Ok(())
+        }
+
+        async fn write(&mut self, words: &[u8]) -> core::result::Result<(), Infallible> {
+            if words[0] & 0xE0 == Command::WREG.word() {
+                let start = (words[0] & 0x1F) as usize;
+                let n = words[1] as usize + 1;
+                self.regs.0[start..start + n].copy_from_slice(&words[2..2 + n]);
+            }
+            Ok(())
+        }
+
+        async fn wait(&mut self, _i: u16) {}
+    }
+
+    /// A nDRDY pin that signals a new sample every time it is awaited
+    struct FakeDrdy<'a>(&'a Cell<u8>);
+
+    impl embedded_hal::digital::ErrorType for FakeDrdy<'_> {
+        type Error = Infallible;
+    }
+
+    impl Wait for FakeDrdy<'_> {
+        async fn wait_for_high(&mut self) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+
+        async fn wait_for_low(&mut self) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+
+        async fn wait_for_rising_edge(&mut self) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+
+        async fn wait_for_falling_edge(&mut self) -> core::result::Result<(), Infallible> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+
+        async fn wait_for_any_edge(&mut self) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn async_registers() {
+        let sample = Cell::new(0);
+        let mut ads = ready(Ads1292::new(FakeAds {
+            regs: RegisterMap([
+                0x53, 0x02, 0x80, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x0C,
+            ]),
+            sample: &sample,
+        }))
+        .unwrap();
+        ready(ads.init()).unwrap();
+        ready(ads.modify_chan1(|chan: &mut ChannelSettings| chan.set_gain(GainSetting::G12)))
+            .unwrap();
+        assert_eq!(ready(ads.read_register(Register::CH1SET)).unwrap(), 0x60);
+
+        ready(ads.enable_register_cache()).unwrap();
+        ready(ads.reset()).unwrap();
+        assert!(ads.cached_registers().is_some());
+    }
+
+    #[test]
+    fn async_stream_waits_for_drdy() {
+        let sample = Cell::new(0);
+        let ads = ready(Ads1292::new(FakeAds {
+            regs: RegisterMap::default(),
+            sample: &sample,
+        }))
+        .unwrap();
+        let drdy = FakeDrdy(&sample);
+        let mut stream = ready(ads.into_data_stream(drdy)).unwrap();
+        assert_eq!(ready(stream.next()).unwrap().channel_1().2, 1);
+        assert_eq!(ready(stream.next()).unwrap().channel_1().2, 2);
+    }
+}
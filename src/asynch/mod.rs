@@ -0,0 +1,324 @@
+//! Async drivers on embedded-hal-async, enabled with the `async` feature.
+//!
+//! These mirror the blocking drivers, but await the bus, the delays in between commands and the
+//! DRDY pin instead of spinning, so the driver can share an executor with other tasks.
+//!
+//! ```norun
+//!// spi: embedded-hal-async SpiDevice, using ads129xx::spi::MODE
+//!// delay: embedded-hal-async DelayNs
+//!// drdy: the nDRDY pin, implementing embedded-hal-async Wait
+//!
+//!let mut ads = Ads1292::new(SpiInterface::new(spi, delay)).await?;
+//!ads.init().await?;
+//!ads.start().await?;
+//!
+//!let mut stream = ads.into_data_stream(drdy).await?;
+//!loop {
+//!    let data = stream.next().await?;
+//!}
+//!```
+
+use core::convert::TryFrom;
+
+use crate::config::{Ads1292Config, ConfigDiff};
+use crate::data::{LeadOffStatus, Scaling};
+use crate::spi::{Timing, WAIT_PERIOD_US};
+use crate::{
+    check_readback, Ads129xxError, Channel, ChannelSettings, Command, Conf1, Conf2, DeviceId,
+    GpioConfig, Loff, LoffSense, RLDSenseSelection, Register, RegisterFrame, RegisterMap,
+    RegisterState, RespConf1, RespConf2, Result, REGISTER_COUNT, RESET_RECOVERY_TCLK,
+};
+
+/// Async ADS1292 driver
+pub mod ads1292;
+
+/// The transport the async drivers use to talk to the device.
+///
+/// Implemented by `spi::SpiInterface` for embedded-hal-async.
+#[allow(async_fn_in_trait)]
+pub trait Interface {
+    /// Bus error
+    type Error;
+
+    /// Transfer the buffer to the device, the passed buffer will contain the read data.
+    async fn transfer(&mut self, buffer: &mut [u8]) -> core::result::Result<(), Self::Error>;
+
+    /// Write a number of bytes to the device.
+    async fn write(&mut self, buffer: &[u8]) -> core::result::Result<(), Self::Error>;
+
+    /// Wait `i` periods of 2 microseconds.
+    async fn wait(&mut self, i: u16);
+
+    /// The delays the interface uses, which also tell the period of the device clock.
+    ///
+    /// Defaults to `Timing::CONSERVATIVE`.
+    #[inline]
+    fn timing(&self) -> Timing {
+        Timing::CONSERVATIVE
+    }
+
+    /// Wait `n` periods of the device clock, rounded up to whole `wait` periods.
+    #[inline]
+    async fn wait_tclk(&mut self, n: u16) {
+        let periods = (u32::from(n) * self.timing().tclk_ns).div_ceil(WAIT_PERIOD_US * 1000);
+        self.wait(u16::try_from(periods).unwrap_or(u16::MAX)).await;
+    }
+}
+
+/// Send a command, including the ones `Ads129xx::cmd` refuses
+#[inline]
+pub(crate) async fn command<DEV: Interface>(spi: &mut DEV, cmd: Command) -> Result<(), DEV::Error> {
+    spi.write(&[cmd.word()]).await.map_err(Into::into)
+}
+
+macro_rules! simple_register {
+    ($read_name:ident, $write_name:ident, $modify_name:ident, $register:ident, $valuetype:ident) => {
+        #[inline]
+        async fn $read_name(&mut self) -> Result<$valuetype, DEV::Error> {
+            Ok($valuetype(self.read_register(Register::$register).await?))
+        }
+        #[inline]
+        async fn $write_name(&mut self, value: &$valuetype) -> Result<(), DEV::Error> {
+            self.write_register(Register::$register, value.0).await
+        }
+        /// Read the register, let `f` modify it, and write it back if it changed
+        #[inline]
+        async fn $modify_name<F: FnOnce(&mut $valuetype)>(
+            &mut self,
+            f: F,
+        ) -> Result<(), DEV::Error> {
+            let old = self.$read_name().await?;
+            let mut new = old;
+            f(&mut new);
+            if new != old {
+                self.$write_name(&new).await?;
+            }
+            Ok(())
+        }
+    };
+}
+
+/// Represents any ADS129xx device, driven asynchronously.
+///
+/// The async counterpart of `crate::Ads129xx`; see there for the behaviour of write
+/// verification and the register cache.
+#[allow(async_fn_in_trait)]
+pub trait Ads129xx<DEV>
+where
+    DEV: Interface,
+{
+    /// Get a mutable reference to the wrapped interface
+    fn spi_device(&mut self) -> &mut DEV;
+
+    /// Consume self and return the wrapped interface
+    fn into_spi_device(self) -> DEV;
+
+    /// Get a reference to the register bookkeeping of the driver
+    fn register_state(&self) -> &RegisterState;
+
+    /// Get a mutable reference to the register bookkeeping of the driver
+    fn register_state_mut(&mut self) -> &mut RegisterState;
+
+    /// Whether register writes are read back and verified
+    #[inline]
+    fn verify_writes(&self) -> bool {
+        self.register_state().verify
+    }
+
+    /// Enable or disable verification of register writes.
+    #[inline]
+    fn set_verify_writes(&mut self, verify: bool) {
+        self.register_state_mut().verify = verify;
+    }
+
+    /// Read the whole register map into a shadow cache, and keep it up to date from then on.
+    async fn enable_register_cache(&mut self) -> Result<(), DEV::Error> {
+        let map = self.dump_registers().await?;
+        self.register_state_mut().cache = Some(map);
+        Ok(())
+    }
+
+    /// Stop caching registers; all reads go to the bus again.
+    #[inline]
+    fn disable_register_cache(&mut self) {
+        self.register_state_mut().cache = None;
+    }
+
+    /// The shadow copy of the register map, if the cache is enabled
+    #[inline]
+    fn cached_registers(&self) -> Option<&RegisterMap> {
+        self.register_state().cache.as_ref()
+    }
+
+    /// Send a command to the ADS129xx.
+    ///
+    /// Fails with `Ads129xxError::RestrictedCommand` for commands that the driver methods
+    /// send, see `Command::is_restricted`.
+    #[inline]
+    async fn cmd(&mut self, cmd: Command) -> Result<(), DEV::Error> {
+        if cmd.is_restricted() {
+            return Err(Ads129xxError::RestrictedCommand(cmd));
+        }
+        command(self.spi_device(), cmd).await
+    }
+
+    #[inline]
+    async fn wait(&mut self, i: u16) {
+        self.spi_device().wait(i).await
+    }
+
+    /// Reset the device with the RESET command, and wait until it accepts commands again.
+    ///
+    /// The device comes out of reset in RDATAC mode, so this sends SDATAC to return to command
+    /// mode. All registers have their default values afterwards; if the register cache is
+    /// enabled, it is reloaded.
+    async fn reset(&mut self) -> Result<(), DEV::Error> {
+        command(self.spi_device(), Command::RESET).await?;
+        self.spi_device().wait_tclk(RESET_RECOVERY_TCLK).await;
+        command(self.spi_device(), Command::SDATAC).await?;
+        if self.cached_registers().is_some() {
+            self.enable_register_cache().await?;
+        }
+        Ok(())
+    }
+
+    /// Read a register of the ADS1292
+    #[inline]
+    async fn read_register(&mut self, reg: Register) -> Result<u8, DEV::Error> {
+        if let Some(value) = self.register_state().cached(reg) {
+            return Ok(value);
+        }
+        let mut value = [0u8; 1];
+        self.read_registers(reg, &mut value).await?;
+        Ok(value[0])
+    }
+
+    /// Write in register of the ADS1292
+    #[inline]
+    async fn write_register(&mut self, reg: Register, data: u8) -> Result<(), DEV::Error> {
+        self.write_registers(reg, &[data]).await
+    }
+
+    /// Read consecutive registers starting at `start` in a single transaction, filling `data`.
+    async fn read_registers(&mut self, start: Register, data: &mut [u8]) -> Result<(), DEV::Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut frame = RegisterFrame::read(start, data.len())?;
+        self.spi_device().transfer(frame.bytes_mut()).await?;
+        data.copy_from_slice(frame.data());
+        self.register_state_mut().record(start, data);
+        Ok(())
+    }
+
+    /// Write consecutive registers starting at `start` in a single transaction.
+    async fn write_registers(&mut self, start: Register, data: &[u8]) -> Result<(), DEV::Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let frame = RegisterFrame::write(start, data)?;
+        self.spi_device().write(frame.bytes()).await?;
+        if self.verify_writes() {
+            let mut read = [0u8; REGISTER_COUNT];
+            let read = &mut read[..data.len()];
+            self.read_registers(start, read).await?;
+            check_readback(start, data, read)?;
+        }
+        self.register_state_mut().record(start, data);
+        Ok(())
+    }
+
+    /// Read the whole register map in a single transaction
+    async fn dump_registers(&mut self) -> Result<RegisterMap, DEV::Error> {
+        let mut map = RegisterMap::default();
+        self.read_registers(Register::ID, &mut map.0).await?;
+        Ok(map)
+    }
+
+    /// Read every writable register into a configuration snapshot
+    async fn read_config(&mut self) -> Result<Ads1292Config, DEV::Error> {
+        Ok(Ads1292Config::from_registers(&self.dump_registers().await?))
+    }
+
+    /// Apply a configuration, only writing the fields that differ from the current one.
+    ///
+    /// Returns the fields that were written.
+    async fn apply_config(&mut self, config: &Ads1292Config) -> Result<ConfigDiff, DEV::Error> {
+        let diff = self.read_config().await?.diff(config);
+        for field in diff.iter() {
+            self.write_register(field.register(), config.field(field))
+                .await?;
+        }
+        Ok(diff)
+    }
+
+    /// Read and decode the ID register
+    #[inline]
+    async fn read_device_id(&mut self) -> Result<DeviceId, DEV::Error> {
+        Ok(DeviceId(self.read_register(Register::ID).await?))
+    }
+
+    /// Read the lead-off status register.
+    #[inline]
+    async fn read_lead_off_status(&mut self) -> Result<LeadOffStatus, DEV::Error> {
+        Ok(LeadOffStatus {
+            status: self.read_register(Register::LOFF_STAT).await?,
+        })
+    }
+
+    /// Read the gain of a channel and the reference selection, to convert its data to volts.
+    async fn read_scaling(&mut self, ch: Channel) -> Result<Option<Scaling>, DEV::Error> {
+        let chan = match ch {
+            Channel::Ch1 => self.read_chan1().await?,
+            Channel::Ch2 => self.read_chan2().await?,
+        };
+        Ok(Scaling::from_registers(chan, self.read_conf2().await?))
+    }
+
+    simple_register!(read_conf1, write_conf1, modify_conf1, CONFIG1, Conf1);
+    simple_register!(read_conf2, write_conf2, modify_conf2, CONFIG2, Conf2);
+    simple_register!(read_loff, write_loff, modify_loff, LOFF, Loff);
+    simple_register!(
+        read_loff_sens,
+        write_loff_sens,
+        modify_loff_sens,
+        LOFF_SENS,
+        LoffSense
+    );
+    simple_register!(
+        read_chan1,
+        write_chan1,
+        modify_chan1,
+        CH1SET,
+        ChannelSettings
+    );
+    simple_register!(
+        read_chan2,
+        write_chan2,
+        modify_chan2,
+        CH2SET,
+        ChannelSettings
+    );
+    simple_register!(
+        read_rld_sens,
+        write_rld_sens,
+        modify_rld_sens,
+        RLD_SENS,
+        RLDSenseSelection
+    );
+    simple_register!(
+        read_resp_conf1,
+        write_resp_conf1,
+        modify_resp_conf1,
+        RESP1,
+        RespConf1
+    );
+    simple_register!(
+        read_resp_conf2,
+        write_resp_conf2,
+        modify_resp_conf2,
+        RESP2,
+        RespConf2
+    );
+    simple_register!(read_gpio, write_gpio, modify_gpio, GPIO, GpioConfig);
+}
@@ -0,0 +1,516 @@
+//! The register types let any bit pattern through, including ones the datasheet forbids.
+//! `ConfigBuilder` starts from the reset values, keeps the reserved bits at their required
+//! values and checks the combination of settings before anything is written to the device.
+//!
+//! ```norun
+//!let config = ConfigBuilder::new(Model::Ads1292)
+//!    .sample_rate(SampleRate::Sps500)
+//!    .reference(Reference::Internal2V42)
+//!    .gain(Channel::Ch1, GainSetting::G6)
+//!    .mux(Channel::Ch2, InputSelection::InputShorted)
+//!    .rld_buffer(true)
+//!    .rld_sense(Channel::Ch1, true, true)
+//!    .build()?;
+//!
+//!ads1292.apply_config(&config)?;
+//!```
+
+use core::fmt;
+
+use crate::config::Ads1292Config;
+use crate::data::Reference;
+use crate::{
+    Channel, ChopFrequency, GainSetting, InputSelection, LeadOffCurrentMagnitude, Model, Register,
+    RespConf1, SampleRate,
+};
+
+/// A configuration that violates the datasheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Bits that must be written with a fixed value do not hold that value
+    ReservedBits { reg: Register, value: u8 },
+    /// The data rate bits hold the reserved value 0b111
+    InvalidSampleRate,
+    /// The gain bits of a channel hold the reserved value 0b111
+    InvalidGain(Channel),
+    /// The input selection of a channel is reserved, or routes IN3 to channel 2
+    InvalidMux(Channel),
+    /// The chop frequency bits hold the reserved value 0b01
+    InvalidChopFrequency,
+    /// The lead-off comparator threshold is out of the 0..=7 range
+    InvalidLeadOffThreshold(u8),
+    /// The respiration phase is out of the 0..=15 range
+    InvalidRespirationPhase(u8),
+    /// A channel measures the test signal, but the test signal is turned off
+    TestSignalDisabled(Channel),
+    /// Lead-off detection is enabled, but the lead-off comparators are powered down
+    LeadOffComparatorsPoweredDown,
+    /// RLD lead-off sensing is enabled, but the RLD buffer is powered down
+    RldBufferPoweredDown,
+    /// Respiration settings were made for a model without a respiration front-end
+    RespirationUnsupported(Model),
+    /// Channel 2 is not bonded out on single-channel models, and CH2SET must be 0x81: powered
+    /// down with its inputs shorted
+    Channel2NotPoweredDown(Model),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ConfigError::*;
+        match self {
+            ReservedBits { reg, value } => {
+                write!(
+                    f,
+                    "reserved bits of {:?} hold an invalid value in {:#04x}",
+                    reg, value
+                )
+            }
+            InvalidSampleRate => write!(f, "data rate 0b111 is reserved"),
+            InvalidGain(ch) => write!(f, "gain 0b111 of {:?} is reserved", ch),
+            InvalidMux(ch) => write!(f, "input selection of {:?} is reserved", ch),
+            InvalidChopFrequency => write!(f, "chop frequency 0b01 is reserved"),
+            InvalidLeadOffThreshold(th) => {
+                write!(
+                    f,
+                    "lead-off comparator threshold {} is out of range 0..=7",
+                    th
+                )
+            }
+            InvalidRespirationPhase(ph) => {
+                write!(f, "respiration phase {} is out of range 0..=15", ph)
+            }
+            TestSignalDisabled(ch) => {
+                write!(f, "{:?} measures the test signal, but it is turned off", ch)
+            }
+            LeadOffComparatorsPoweredDown => {
+                write!(
+                    f,
+                    "lead-off detection needs the lead-off comparators powered up"
+                )
+            }
+            RldBufferPoweredDown => {
+                write!(f, "RLD lead-off sensing needs the RLD buffer powered up")
+            }
+            RespirationUnsupported(model) => {
+                write!(f, "{:?} has no respiration front-end", model)
+            }
+            Channel2NotPoweredDown(model) => {
+                write!(
+                    f,
+                    "channel 2 must be powered down with its inputs shorted on the {:?}",
+                    model
+                )
+            }
+        }
+    }
+}
+
+/// CH2SET on single-channel models: powered down, with its inputs shorted
+const SINGLE_CHANNEL_CH2SET: u8 = 0x81;
+
+fn has_respiration(model: Model) -> bool {
+    model == Model::Ads1292R
+}
+
+fn single_channel(model: Model) -> bool {
+    matches!(model, Model::Ads1191 | Model::Ads1291)
+}
+
+/// The bits of a register that must hold a fixed value, and that value, as `(mask, value)`.
+fn reserved_bits(model: Model, reg: Register) -> (u8, u8) {
+    match reg {
+        Register::CONFIG1 => (0x78, 0x00),
+        Register::CONFIG2 => (0x84, 0x80),
+        Register::LOFF => (0x12, 0x10),
+        Register::LOFF_SENS => (0xC0, 0x00),
+        Register::LOFF_STAT => (0xBF, 0x00),
+        Register::RESP1 if has_respiration(model) => (0x02, 0x02),
+        Register::RESP1 => (0xFF, 0x02),
+        Register::RESP2 if has_respiration(model) => (0x79, 0x00),
+        Register::RESP2 => (0x7D, 0x04),
+        Register::GPIO => (0xF0, 0x00),
+        _ => (0x00, 0x00),
+    }
+}
+
+/// Check a configuration against the datasheet rules for a model.
+pub fn validate(model: Model, config: &Ads1292Config) -> Result<(), ConfigError> {
+    if !has_respiration(model) && config.resp_conf1.0 & !0x02 != 0 {
+        return Err(ConfigError::RespirationUnsupported(model));
+    }
+
+    for &reg in Register::ALL.iter() {
+        if let Some(value) = config.register(reg) {
+            let (mask, fixed) = reserved_bits(model, reg);
+            if value & mask != fixed {
+                return Err(ConfigError::ReservedBits { reg, value });
+            }
+        }
+    }
+
+    if matches!(config.conf1.oversampling(), SampleRate::Unknown) {
+        return Err(ConfigError::InvalidSampleRate);
+    }
+
+    for &(ch, settings) in [(Channel::Ch1, config.chan1), (Channel::Ch2, config.chan2)].iter() {
+        if matches!(settings.gain(), GainSetting::Unknown) {
+            return Err(ConfigError::InvalidGain(ch));
+        }
+        match settings.mux() {
+            InputSelection::Unknown => return Err(ConfigError::InvalidMux(ch)),
+            InputSelection::Channel3 if ch == Channel::Ch2 => {
+                return Err(ConfigError::InvalidMux(ch))
+            }
+            InputSelection::TestSignal if !settings.pd() && !config.conf2.int_test() => {
+                return Err(ConfigError::TestSignalDisabled(ch))
+            }
+            _ => (),
+        }
+    }
+
+    if single_channel(model) && config.chan2.0 != SINGLE_CHANNEL_CH2SET {
+        return Err(ConfigError::Channel2NotPoweredDown(model));
+    }
+
+    if matches!(config.rld_sens.chop(), ChopFrequency::Unknown) {
+        return Err(ConfigError::InvalidChopFrequency);
+    }
+
+    if config.loff_sens.0 & 0x0F != 0 && !config.conf2.pdb_loff_comp() {
+        return Err(ConfigError::LeadOffComparatorsPoweredDown);
+    }
+
+    if config.rld_sens.rld_loff_sense() && !config.rld_sens.pdb_rld() {
+        return Err(ConfigError::RldBufferPoweredDown);
+    }
+
+    Ok(())
+}
+
+/// Builds an `Ads1292Config` that respects the datasheet
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigBuilder {
+    model: Model,
+    config: Ads1292Config,
+    error: Option<ConfigError>,
+}
+
+impl ConfigBuilder {
+    /// Start from the reset values, with the reserved bits set as required for the model.
+    ///
+    /// On single-channel models, channel 2 starts out powered down with its inputs shorted.
+    pub fn new(model: Model) -> Self {
+        let mut config = Ads1292Config {
+            resp_conf1: RespConf1(0x02),
+            ..Ads1292Config::default()
+        };
+        if !has_respiration(model) {
+            config.resp_conf2.set_resp_freq_64khz(true);
+        }
+        if single_channel(model) {
+            config.chan2.set_pd(true);
+            config.chan2.set_mux(InputSelection::InputShorted);
+        }
+        ConfigBuilder {
+            model,
+            config,
+            error: None,
+        }
+    }
+
+    /// Start from an existing configuration, for instance one read from the device.
+    pub fn from_config(model: Model, config: Ads1292Config) -> Self {
+        ConfigBuilder {
+            model,
+            config,
+            error: None,
+        }
+    }
+
+    fn fail(mut self, error: ConfigError) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+
+    /// Set the oversampling rate used by all channels
+    pub fn sample_rate(mut self, rate: SampleRate) -> Self {
+        self.config.conf1.set_oversampling(rate);
+        self
+    }
+
+    /// Use single-shot instead of continuous conversion
+    pub fn single_shot(mut self, single_shot: bool) -> Self {
+        self.config.conf1.set_single_shot(single_shot);
+        self
+    }
+
+    fn channel(&mut self, ch: Channel) -> &mut crate::ChannelSettings {
+        match ch {
+            Channel::Ch1 => &mut self.config.chan1,
+            Channel::Ch2 => &mut self.config.chan2,
+        }
+    }
+
+    /// Set the PGA gain of a channel
+    pub fn gain(mut self, ch: Channel, gain: GainSetting) -> Self {
+        self.channel(ch).set_gain(gain);
+        self
+    }
+
+    /// Set the input selection of a channel
+    pub fn mux(mut self, ch: Channel, mux: InputSelection) -> Self {
+        self.channel(ch).set_mux(mux);
+        self
+    }
+
+    /// Power a channel down or up
+    pub fn power_down(mut self, ch: Channel, pd: bool) -> Self {
+        self.channel(ch).set_pd(pd);
+        self
+    }
+
+    /// Select the reference voltage.
+    ///
+    /// An external reference on VREFP powers the internal reference buffer down; its voltage
+    /// does not show in the registers.
+    pub fn reference(mut self, reference: Reference) -> Self {
+        let conf2 = &mut self.config.conf2;
+        conf2.set_pdb_refbuf(!matches!(reference, Reference::External(_)));
+        conf2.set_vref_4v(reference == Reference::Internal4V033);
+        self
+    }
+
+    /// Output the internal oscillator on the CLK pin
+    pub fn clock_output(mut self, enabled: bool) -> Self {
+        self.config.conf2.set_clk_en(enabled);
+        self
+    }
+
+    /// Set CLK_DIV, which must be set when running from a 2.048 MHz clock
+    pub fn clk_div(mut self, clk_div: bool) -> Self {
+        self.config.clk_div = clk_div;
+        self
+    }
+
+    /// Turn the internal test signal on or off, as a 1 Hz square wave or a DC signal
+    pub fn test_signal(mut self, enabled: bool, square_1hz: bool) -> Self {
+        self.config.conf2.set_int_test(enabled);
+        self.config.conf2.set_test_freq(square_1hz);
+        self
+    }
+
+    /// Power the lead-off comparators up or down
+    pub fn lead_off_comparators(mut self, enabled: bool) -> Self {
+        self.config.conf2.set_pdb_loff_comp(enabled);
+        self
+    }
+
+    /// Set the lead-off comparator threshold, 0 (95%) to 7 (70%)
+    pub fn lead_off_threshold(mut self, threshold: u8) -> Self {
+        if threshold > 7 {
+            return self.fail(ConfigError::InvalidLeadOffThreshold(threshold));
+        }
+        self.config.loff.set_comp_th(threshold);
+        self
+    }
+
+    /// Set the lead-off current magnitude
+    pub fn lead_off_current(mut self, current: LeadOffCurrentMagnitude) -> Self {
+        self.config.loff.set_ilead_off(current);
+        self
+    }
+
+    /// Select ac (true) or dc (false) lead-off detection
+    pub fn lead_off_ac(mut self, ac: bool) -> Self {
+        self.config.loff.set_flead_off(ac);
+        self
+    }
+
+    /// Select the inputs of a channel used for lead-off detection
+    pub fn lead_off_sense(mut self, ch: Channel, positive: bool, negative: bool) -> Self {
+        let loff_sens = &mut self.config.loff_sens;
+        match ch {
+            Channel::Ch1 => {
+                loff_sens.set_loff1p(positive);
+                loff_sens.set_loff1n(negative);
+            }
+            Channel::Ch2 => {
+                loff_sens.set_loff2p(positive);
+                loff_sens.set_loff2n(negative);
+            }
+        }
+        self
+    }
+
+    /// Flip the direction of the lead-off current of a channel
+    pub fn lead_off_flip(mut self, ch: Channel, flip: bool) -> Self {
+        match ch {
+            Channel::Ch1 => self.config.loff_sens.set_flip1(flip),
+            Channel::Ch2 => self.config.loff_sens.set_flip2(flip),
+        }
+        self
+    }
+
+    /// Power the right leg drive buffer up or down
+    pub fn rld_buffer(mut self, enabled: bool) -> Self {
+        self.config.rld_sens.set_pbd_rld(enabled);
+        self
+    }
+
+    /// Select the inputs of a channel used for right leg drive derivation
+    pub fn rld_sense(mut self, ch: Channel, positive: bool, negative: bool) -> Self {
+        let rld_sens = &mut self.config.rld_sens;
+        match ch {
+            Channel::Ch1 => {
+                rld_sens.set_rld1p(positive);
+                rld_sens.set_rld1n(negative);
+            }
+            Channel::Ch2 => {
+                rld_sens.set_rld2p(positive);
+                rld_sens.set_rld2n(negative);
+            }
+        }
+        self
+    }
+
+    /// Enable the RLD lead-off sense function
+    pub fn rld_lead_off_sense(mut self, enabled: bool) -> Self {
+        self.config.rld_sens.set_rld_loff_sense(enabled);
+        self
+    }
+
+    /// Generate RLDREF internally as (AVDD – AVSS) / 2, instead of feeding it externally
+    pub fn rld_ref_internal(mut self, internal: bool) -> Self {
+        self.config.resp_conf2.set_rldref_int(internal);
+        self
+    }
+
+    /// Set the PGA chop frequency
+    pub fn chop(mut self, chop: ChopFrequency) -> Self {
+        self.config.rld_sens.set_chop(chop);
+        self
+    }
+
+    /// Configure the respiration front-end (ADS1292R only).
+    ///
+    /// `phase` is in steps of 11.25° at 32 kHz or 22.5° at 64 kHz.
+    pub fn respiration(mut self, demodulation: bool, modulation: bool, phase: u8) -> Self {
+        if !has_respiration(self.model) {
+            return self.fail(ConfigError::RespirationUnsupported(self.model));
+        }
+        if phase > 15 {
+            return self.fail(ConfigError::InvalidRespirationPhase(phase));
+        }
+        let resp_conf1 = &mut self.config.resp_conf1;
+        resp_conf1.set_resp_demod_en1(demodulation);
+        resp_conf1.set_resp_mod_en(modulation);
+        resp_conf1.set_resp_ph(phase);
+        self
+    }
+
+    /// Select a 64 kHz (true) or 32 kHz (false) respiration modulation frequency (ADS1292R only)
+    pub fn respiration_64khz(mut self, freq_64khz: bool) -> Self {
+        if !has_respiration(self.model) {
+            return self.fail(ConfigError::RespirationUnsupported(self.model));
+        }
+        self.config.resp_conf2.set_resp_freq_64khz(freq_64khz);
+        self
+    }
+
+    /// Validate the configuration and return it.
+    ///
+    /// Fails with the first invalid setting that was made, or the first datasheet rule
+    /// the combination of settings violates.
+    pub fn build(self) -> Result<Ads1292Config, ConfigError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        validate(self.model, &self.config)?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_values_are_valid() {
+        for &model in [Model::Ads1291, Model::Ads1292, Model::Ads1292R].iter() {
+            assert!(ConfigBuilder::new(model).build().is_ok());
+        }
+        // The raw reset values do not set the bits the datasheet requires
+        assert_eq!(
+            validate(Model::Ads1292, &Ads1292Config::default()),
+            Err(ConfigError::ReservedBits {
+                reg: Register::RESP1,
+                value: 0x00
+            })
+        );
+    }
+
+    #[test]
+    fn reference_matches_scaling() {
+        use crate::data::Scaling;
+
+        let builder = ConfigBuilder::new(Model::Ads1292).gain(Channel::Ch1, GainSetting::G6);
+        let config = builder.reference(Reference::Internal4V033).build().unwrap();
+        assert_eq!(
+            Scaling::from_registers(config.chan1, config.conf2),
+            Scaling::new(Reference::Internal4V033, GainSetting::G6)
+        );
+        let config = builder
+            .reference(Reference::External(2_500_000))
+            .build()
+            .unwrap();
+        assert_eq!(Scaling::from_registers(config.chan1, config.conf2), None);
+    }
+
+    #[test]
+    fn rejects_invalid_combinations() {
+        let builder = ConfigBuilder::new(Model::Ads1292);
+        assert_eq!(
+            builder
+                .mux(Channel::Ch2, InputSelection::TestSignal)
+                .build(),
+            Err(ConfigError::TestSignalDisabled(Channel::Ch2))
+        );
+        assert!(builder
+            .mux(Channel::Ch2, InputSelection::TestSignal)
+            .test_signal(true, true)
+            .build()
+            .is_ok());
+        assert_eq!(
+            builder.mux(Channel::Ch2, InputSelection::Channel3).build(),
+            Err(ConfigError::InvalidMux(Channel::Ch2))
+        );
+        assert_eq!(
+            builder.lead_off_sense(Channel::Ch1, true, true).build(),
+            Err(ConfigError::LeadOffComparatorsPoweredDown)
+        );
+        assert_eq!(
+            builder.sample_rate(SampleRate::Unknown).build(),
+            Err(ConfigError::InvalidSampleRate)
+        );
+        assert_eq!(
+            builder.lead_off_threshold(8).build(),
+            Err(ConfigError::InvalidLeadOffThreshold(8))
+        );
+        assert_eq!(
+            builder.respiration(true, true, 0).build(),
+            Err(ConfigError::RespirationUnsupported(Model::Ads1292))
+        );
+        assert_eq!(
+            ConfigBuilder::new(Model::Ads1291)
+                .power_down(Channel::Ch2, false)
+                .build(),
+            Err(ConfigError::Channel2NotPoweredDown(Model::Ads1291))
+        );
+        assert_eq!(
+            ConfigBuilder::new(Model::Ads1291)
+                .gain(Channel::Ch2, GainSetting::G1)
+                .build(),
+            Err(ConfigError::Channel2NotPoweredDown(Model::Ads1291))
+        );
+    }
+}
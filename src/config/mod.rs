@@ -0,0 +1,271 @@
+/// Datasheet-validating configuration builder
+pub mod builder;
+
+use crate::{
+    ChannelSettings, Conf1, Conf2, GpioConfig, Loff, LoffSense, RLDSenseSelection, Register,
+    RegisterMap, RespConf1, RespConf2,
+};
+
+/// Mask of the writable CLK_DIV bit in the LOFF_STAT register
+const CLK_DIV: u8 = 1 << 6;
+
+/// Snapshot of every writable register of an ADS1292.
+///
+/// The ADS1291 and ADS1292R share this register map, so this applies to them as well.
+/// The `Default` value holds the register reset values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ads1292Config {
+    pub conf1: Conf1,
+    pub conf2: Conf2,
+    pub loff: Loff,
+    pub chan1: ChannelSettings,
+    pub chan2: ChannelSettings,
+    pub rld_sens: RLDSenseSelection,
+    pub loff_sens: LoffSense,
+    /// The CLK_DIV bit of the LOFF_STAT register; the other bits are read-only status.
+    pub clk_div: bool,
+    pub resp_conf1: RespConf1,
+    pub resp_conf2: RespConf2,
+    pub gpio: GpioConfig,
+}
+
+impl Default for Ads1292Config {
+    fn default() -> Self {
+        Ads1292Config {
+            conf1: Conf1(0x02),
+            conf2: Conf2(0x80),
+            loff: Loff(0x10),
+            chan1: ChannelSettings(0x00),
+            chan2: ChannelSettings(0x00),
+            rld_sens: RLDSenseSelection(0x00),
+            loff_sens: LoffSense(0x00),
+            clk_div: false,
+            resp_conf1: RespConf1(0x00),
+            resp_conf2: RespConf2(0x02),
+            gpio: GpioConfig(0x0C),
+        }
+    }
+}
+
+impl Ads1292Config {
+    /// Extract the configuration from a register map
+    pub fn from_registers(map: &RegisterMap) -> Self {
+        Ads1292Config {
+            conf1: Conf1(map[Register::CONFIG1]),
+            conf2: Conf2(map[Register::CONFIG2]),
+            loff: Loff(map[Register::LOFF]),
+            chan1: ChannelSettings(map[Register::CH1SET]),
+            chan2: ChannelSettings(map[Register::CH2SET]),
+            rld_sens: RLDSenseSelection(map[Register::RLD_SENS]),
+            loff_sens: LoffSense(map[Register::LOFF_SENS]),
+            clk_div: map[Register::LOFF_STAT] & CLK_DIV != 0,
+            resp_conf1: RespConf1(map[Register::RESP1]),
+            resp_conf2: RespConf2(map[Register::RESP2]),
+            gpio: GpioConfig(map[Register::GPIO]),
+        }
+    }
+
+    /// The value this configuration holds for a register.
+    ///
+    /// Returns `None` for the read-only ID register.
+    pub fn register(&self, reg: Register) -> Option<u8> {
+        ConfigField::ALL
+            .iter()
+            .find(|field| field.register() == reg)
+            .map(|&field| self.field(field))
+    }
+
+    /// The value of a field, as it is written to its register
+    pub fn field(&self, field: ConfigField) -> u8 {
+        match field {
+            ConfigField::Conf1 => self.conf1.0,
+            ConfigField::Conf2 => self.conf2.0,
+            ConfigField::Loff => self.loff.0,
+            ConfigField::Chan1 => self.chan1.0,
+            ConfigField::Chan2 => self.chan2.0,
+            ConfigField::RldSens => self.rld_sens.0,
+            ConfigField::LoffSens => self.loff_sens.0,
+            ConfigField::ClkDiv => {
+                if self.clk_div {
+                    CLK_DIV
+                } else {
+                    0
+                }
+            }
+            ConfigField::RespConf1 => self.resp_conf1.0,
+            ConfigField::RespConf2 => self.resp_conf2.0,
+            ConfigField::Gpio => self.gpio.0,
+        }
+    }
+
+    /// List the fields for which `other` holds a different value than `self`.
+    ///
+    /// The GPIO data bits of pins that `other` configures as inputs are not compared, as they
+    /// read back the live pin level rather than a setting.
+    pub fn diff(&self, other: &Ads1292Config) -> ConfigDiff {
+        let mut diff = ConfigDiff::default();
+        for &field in ConfigField::ALL.iter() {
+            let mask = field.register().readback_mask(other.field(field));
+            if self.field(field) & mask != other.field(field) & mask {
+                diff.insert(field);
+            }
+        }
+        diff
+    }
+}
+
+/// A field of `Ads1292Config`.
+///
+/// Every field is stored in its own register, so a changed field is written as one register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigField {
+    Conf1,
+    Conf2,
+    Loff,
+    Chan1,
+    Chan2,
+    RldSens,
+    LoffSens,
+    ClkDiv,
+    RespConf1,
+    RespConf2,
+    Gpio,
+}
+
+impl ConfigField {
+    /// All fields, in register address order
+    pub const ALL: [ConfigField; 11] = [
+        ConfigField::Conf1,
+        ConfigField::Conf2,
+        ConfigField::Loff,
+        ConfigField::Chan1,
+        ConfigField::Chan2,
+        ConfigField::RldSens,
+        ConfigField::LoffSens,
+        ConfigField::ClkDiv,
+        ConfigField::RespConf1,
+        ConfigField::RespConf2,
+        ConfigField::Gpio,
+    ];
+
+    /// The register holding this field
+    pub fn register(self) -> Register {
+        match self {
+            ConfigField::Conf1 => Register::CONFIG1,
+            ConfigField::Conf2 => Register::CONFIG2,
+            ConfigField::Loff => Register::LOFF,
+            ConfigField::Chan1 => Register::CH1SET,
+            ConfigField::Chan2 => Register::CH2SET,
+            ConfigField::RldSens => Register::RLD_SENS,
+            ConfigField::LoffSens => Register::LOFF_SENS,
+            ConfigField::ClkDiv => Register::LOFF_STAT,
+            ConfigField::RespConf1 => Register::RESP1,
+            ConfigField::RespConf2 => Register::RESP2,
+            ConfigField::Gpio => Register::GPIO,
+        }
+    }
+
+    /// The name of the field in `Ads1292Config`, for logging
+    pub fn name(self) -> &'static str {
+        match self {
+            ConfigField::Conf1 => "conf1",
+            ConfigField::Conf2 => "conf2",
+            ConfigField::Loff => "loff",
+            ConfigField::Chan1 => "chan1",
+            ConfigField::Chan2 => "chan2",
+            ConfigField::RldSens => "rld_sens",
+            ConfigField::LoffSens => "loff_sens",
+            ConfigField::ClkDiv => "clk_div",
+            ConfigField::RespConf1 => "resp_conf1",
+            ConfigField::RespConf2 => "resp_conf2",
+            ConfigField::Gpio => "gpio",
+        }
+    }
+}
+
+/// Set of fields that differ between two configurations
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigDiff(u16);
+
+impl ConfigDiff {
+    /// Add a field to the set
+    pub fn insert(&mut self, field: ConfigField) {
+        self.0 |= 1 << field as u8;
+    }
+
+    /// Whether the field is part of the set
+    pub fn contains(&self, field: ConfigField) -> bool {
+        self.0 & 1 << field as u8 != 0
+    }
+
+    /// Whether the configurations were equal
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Number of fields that differ
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Iterate over the fields that differ, in register address order
+    pub fn iter(&self) -> impl Iterator<Item = ConfigField> {
+        let diff = *self;
+        ConfigField::ALL
+            .iter()
+            .copied()
+            .filter(move |&field| diff.contains(field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_map_round_trip() {
+        let mut map = RegisterMap([
+            0x53, 0x03, 0xA0, 0x10, 0x60, 0x81, 0x2C, 0x0F, 0x5F, 0xF2, 0x87, 0x0C,
+        ]);
+        let config = Ads1292Config::from_registers(&map);
+        assert!(config.clk_div);
+        assert_eq!(config.register(Register::ID), None);
+        assert_eq!(config.register(Register::LOFF_STAT), Some(0x40));
+
+        map[Register::LOFF_STAT] = 0x40;
+        for &reg in Register::ALL[1..].iter() {
+            assert_eq!(config.register(reg), Some(map[reg]));
+        }
+    }
+
+    #[test]
+    fn diff_lists_changed_fields() {
+        let reset = Ads1292Config::default();
+        assert!(reset.diff(&reset).is_empty());
+
+        let mut config = reset;
+        config.chan2.set_pd(true);
+        config.clk_div = true;
+        let diff = reset.diff(&config);
+        assert_eq!(diff.len(), 2);
+        assert!(diff.contains(ConfigField::Chan2));
+        assert!(diff.contains(ConfigField::ClkDiv));
+
+        // Both GPIO pins are inputs, so their levels are not part of the configuration
+        config.gpio.0 |= 0x03;
+        assert_eq!(reset.diff(&config).len(), 2);
+
+        let mut iter = diff.iter();
+        let field = iter.next().unwrap();
+        assert_eq!(
+            (field.name(), field.register()),
+            ("chan2", Register::CH2SET)
+        );
+        let field = iter.next().unwrap();
+        assert_eq!(
+            (field.name(), field.register()),
+            ("clk_div", Register::LOFF_STAT)
+        );
+        assert!(iter.next().is_none());
+    }
+}
@@ -1,13 +1,56 @@
 use core::fmt;
 
+use crate::{Ads129xxError, ChannelSettings, Conf2, GainSetting, GpioConfig, Result};
+
+/// Mask of the sync nibble in the first byte of a data block
+const SYNC_MASK: u8 = 0xF0;
+/// Every status word starts with 1100
+const SYNC: u8 = 0xC0;
+
+/// The internal 2.42 V reference, in microvolts
+const VREF_2V42_UV: u32 = 2_420_000;
+/// The internal 4.033 V reference, in microvolts
+const VREF_4V033_UV: u32 = 4_033_000;
+/// Codes per reference voltage: the 24-bit two's complement output spans -VREF to +VREF
+const FULL_SCALE: i32 = 0x800_000;
+
+/// Output of the temperature sensor at 25 degrees Celsius, in nanovolts
+const TEMP_OFFSET_NV: i64 = 145_300_000;
+/// Slope of the temperature sensor, in nanovolts per millidegree Celsius
+const TEMP_SLOPE_NV: i64 = 490;
+
+/// Blocks to read while resynchronising a stream, before giving up
+pub(crate) const RESYNC_ATTEMPTS: usize = 4;
+
+/// Whether the first byte of a data block starts with the sync nibble of the status word
+pub(crate) fn is_synced(status: u8) -> bool {
+    status & SYNC_MASK == SYNC
+}
+
+/// A data block that does not start with the sync nibble of the status word; carries the first
+/// byte of the block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Desync(pub u8);
+
+/// Check the sync nibble of a data block that was just read
+pub(crate) fn check_sync<E, PinE>(status: u8) -> Result<(), E, PinE> {
+    if !is_synced(status) {
+        return Err(Ads129xxError::FrameDesync(status));
+    }
+    Ok(())
+}
+
+/// Lead-off status, either from the status word of a data block or from the LOFF_STAT register
 #[derive(Default, Copy, Clone, Debug)]
 pub struct LeadOffStatus {
-    /// The status. Bits [5:7] are unused
+    /// The status. Bits 5 and 7 are unused
     pub status: u8,
 }
 
 impl LeadOffStatus {
     /// Clock divider selection
+    ///
+    /// Only meaningful when read from the LOFF_STAT register, data blocks do not carry this bit.
     pub fn clk_div(&self) -> u8 {
         self.status >> 6 & 1
     }
@@ -81,6 +124,14 @@ impl GpioStatus {
     }
 }
 
+impl From<GpioConfig> for GpioStatus {
+    fn from(config: GpioConfig) -> Self {
+        GpioStatus {
+            status: config.0 & 0x0F,
+        }
+    }
+}
+
 impl fmt::Display for GpioStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -98,22 +149,193 @@ impl fmt::Display for GpioStatus {
 pub struct ChannelData(pub u8, pub u8, pub u8);
 
 impl ChannelData {
+    /// Whether the code is at positive or negative full scale, so the input may be beyond the
+    /// range of the ADC
+    pub fn is_saturated(self) -> bool {
+        let code: i32 = self.into();
+        code == 0x7F_FFFF || code == -0x80_0000
+    }
+
     /// Converts this channel's data into temperature in degrees Celcius (page 19)
+    #[deprecated(
+        note = "treats codes as microvolts, use `temperature_millicelsius` or `Ads1292::measure_temperature`"
+    )]
     pub fn temp(self) -> i32 {
         let units: i32 = self.into();
         (units - 145_300) / 490 + 25
     }
 
+    #[deprecated(note = "assumes a 2.4 V reference and a gain of 1, use `Scaling` instead")]
     pub fn millivolts(self) -> f32 {
         let units: i32 = self.into();
         (units as f32 * 2400.) / 0x800_000 as f32
     }
 
+    #[deprecated(note = "assumes a 2.4 V reference and a gain of 1, use `Scaling` instead")]
     pub fn from_millivolts(mv: f32) -> Self {
         ((mv * (0x800_000 as f32) / 2400.) as i32).into()
     }
 }
 
+/// The reference voltage of the ADC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    /// The internal 2.42 V reference
+    Internal2V42,
+    /// The internal 4.033 V reference, which requires an analog supply of at least 4.4 V
+    Internal4V033,
+    /// An external reference on VREFP, in microvolts
+    External(u32),
+}
+
+impl Reference {
+    /// The reference voltage, in microvolts
+    pub fn microvolts(self) -> u32 {
+        match self {
+            Reference::Internal2V42 => VREF_2V42_UV,
+            Reference::Internal4V033 => VREF_4V033_UV,
+            Reference::External(uv) => uv,
+        }
+    }
+}
+
+/// Converts the data of a channel to the voltage at its electrodes, and back.
+///
+/// The conversion depends on the PGA gain of the channel and on the reference voltage of the
+/// ADC: a code of 1 is VREF / (gain * 2^23). Build it from the register values with
+/// `from_registers`, or read them with `Ads129xx::read_scaling`. Both are stale once CONFIG2 or
+/// the channel's settings change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scaling {
+    vref_uv: u32,
+    gain: u8,
+}
+
+impl Scaling {
+    /// Scaling for a reference and a PGA gain setting, like an external reference on VREFP.
+    ///
+    /// Returns `None` if the gain is the reserved setting, or if an external reference is 0 V.
+    pub fn new(reference: Reference, gain: GainSetting) -> Option<Self> {
+        let vref_uv = reference.microvolts();
+        if vref_uv == 0 {
+            return None;
+        }
+        Some(Scaling {
+            vref_uv,
+            gain: gain.factor()?,
+        })
+    }
+
+    /// Scaling for a channel with the given settings, and the reference selected by CONFIG2.
+    ///
+    /// Returns `None` if the gain is the reserved setting, or if the internal reference buffer
+    /// is powered down so that the reference comes from VREFP; use `new` for the latter.
+    pub fn from_registers(chan: ChannelSettings, conf2: Conf2) -> Option<Self> {
+        if !conf2.pdb_refbuf() {
+            return None;
+        }
+        let reference = if conf2.vref_4v() {
+            Reference::Internal4V033
+        } else {
+            Reference::Internal2V42
+        };
+        Scaling::new(reference, chan.gain())
+    }
+
+    /// The reference voltage, in microvolts
+    pub fn vref_uv(&self) -> u32 {
+        self.vref_uv
+    }
+
+    /// The PGA gain factor
+    pub fn gain(&self) -> u8 {
+        self.gain
+    }
+
+    /// The voltage at the electrodes, in microvolts
+    pub fn microvolts(&self, data: ChannelData) -> f32 {
+        let units: i32 = data.into();
+        units as f32 * self.vref_uv as f32 / (f32::from(self.gain) * FULL_SCALE as f32)
+    }
+
+    /// The code the channel would output for a voltage at the electrodes, rounded to the
+    /// nearest code and clamped to the output range. Useful to generate test stimuli.
+    pub fn from_microvolts(&self, uv: f32) -> ChannelData {
+        let units = uv * f32::from(self.gain) * FULL_SCALE as f32 / self.vref_uv as f32;
+        // Round half away from zero; the cast saturates
+        let units = if units < 0. { units - 0.5 } else { units + 0.5 } as i32;
+        units.clamp(-FULL_SCALE, FULL_SCALE - 1).into()
+    }
+
+    /// The voltage at the electrodes, in nanovolts rounded to the nearest nanovolt.
+    ///
+    /// Integer-only equivalent of `microvolts`, for targets without an FPU. Returns an `i64`, as
+    /// full scale at a gain of 1 exceeds `i32::MAX` nanovolts.
+    pub fn nanovolts(&self, data: ChannelData) -> i64 {
+        let units = i64::from(i32::from(data));
+        div_round(
+            units * self.vref_nv(),
+            i64::from(self.gain) * i64::from(FULL_SCALE),
+        )
+    }
+
+    /// Integer-only equivalent of `from_microvolts`, taking nanovolts
+    pub fn from_nanovolts(&self, nv: i64) -> ChannelData {
+        // Anything beyond full scale clamps to the same code, and this keeps the product in range
+        let limit = self.vref_nv() / i64::from(self.gain) + 1;
+        let nv = nv.clamp(-limit, limit);
+        let units = div_round(
+            nv * i64::from(self.gain) * i64::from(FULL_SCALE),
+            self.vref_nv(),
+        );
+        (units.clamp(-i64::from(FULL_SCALE), i64::from(FULL_SCALE) - 1) as i32).into()
+    }
+
+    fn vref_nv(&self) -> i64 {
+        i64::from(self.vref_uv) * 1000
+    }
+}
+
+/// Supply voltages, as measured through the MVDD input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Supply {
+    /// Analog supply, AVDD - AVSS, in microvolts
+    pub avdd_uv: u32,
+    /// Digital supply, in microvolts
+    pub dvdd_uv: u32,
+}
+
+impl Supply {
+    /// The minimal supply voltages in the Recommended Operating Conditions of the datasheet:
+    /// 2.7 V for AVDD - AVSS and 1.7 V for DVDD. Note that the 4.033 V reference requires an
+    /// analog supply of at least 4.4 V.
+    pub const MIN: Supply = Supply {
+        avdd_uv: 2_700_000,
+        dvdd_uv: 1_700_000,
+    };
+
+    /// Whether either supply is below that of `threshold`. Use a threshold with some margin
+    /// above `MIN` to warn before the device browns out.
+    pub fn is_low(&self, threshold: &Supply) -> bool {
+        self.avdd_uv < threshold.avdd_uv || self.dvdd_uv < threshold.dvdd_uv
+    }
+}
+
+/// Convert the voltage of the temperature sensor, as returned by `Scaling::nanovolts`, to
+/// millidegrees Celsius (page 19).
+pub fn temperature_millicelsius(nv: i64) -> i32 {
+    (div_round(nv - TEMP_OFFSET_NV, TEMP_SLOPE_NV) + 25_000) as i32
+}
+
+/// Divide, rounding half away from zero like the float conversions. `den` must be positive.
+pub(crate) fn div_round(num: i64, den: i64) -> i64 {
+    if num < 0 {
+        (num - den / 2) / den
+    } else {
+        (num + den / 2) / den
+    }
+}
+
 impl From<ChannelData> for i32 {
     fn from(channel_data: ChannelData) -> Self {
         i32::from_be_bytes([channel_data.0, channel_data.1, channel_data.2, 0]) >> 8
@@ -138,6 +360,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(deprecated)]
     fn bijective_millivolts() {
         let c1: ChannelData = ChannelData::from_millivolts(10.9883);
         let c2: ChannelData = ChannelData::from_millivolts(-9.32);
@@ -147,6 +370,80 @@ mod tests {
         assert_eq!(c3, ChannelData::from_millivolts(c3.millivolts()));
     }
 
+    #[test]
+    fn scaling_from_registers() {
+        let mut chan = ChannelSettings(0x00);
+        chan.set_gain(GainSetting::G12);
+        let mut conf2 = Conf2(0x80);
+        assert_eq!(Scaling::from_registers(chan, conf2), None);
+
+        conf2.set_pdb_refbuf(true);
+        conf2.set_vref_4v(true);
+        let scaling = Scaling::from_registers(chan, conf2).unwrap();
+        assert_eq!(
+            Some(scaling),
+            Scaling::new(Reference::Internal4V033, GainSetting::G12)
+        );
+        assert_eq!(Scaling::new(Reference::External(0), GainSetting::G1), None);
+        assert_eq!(
+            Scaling::new(Reference::Internal2V42, GainSetting::Unknown),
+            None
+        );
+
+        // Full scale is +-VREF / gain
+        let max = scaling.microvolts(ChannelData::from(0x7F_FFFF));
+        assert!((max - 4_033_000. / 12.).abs() < 0.1);
+        let c = ChannelData::from(-12_345);
+        assert_eq!(scaling.from_microvolts(scaling.microvolts(c)), c);
+        assert_eq!(scaling.from_microvolts(1e9), ChannelData::from(0x7F_FFFF));
+    }
+
+    #[test]
+    fn fixed_point_agrees_with_float() {
+        let codes = [
+            -0x80_0000, -1_234_567, -4_321, -1, 0, 1, 77, 654_321, 0x7F_FFFF,
+        ];
+        for &reference in &[Reference::Internal2V42, Reference::Internal4V033] {
+            for bits in 0..7 {
+                let scaling = Scaling::new(reference, GainSetting::from(bits)).unwrap();
+                let (vref_uv, gain) = (scaling.vref_uv(), scaling.gain());
+                let lsb_nv = f64::from(vref_uv) * 1000. / (f64::from(gain) * 8_388_608.);
+                for &code in &codes {
+                    let c = ChannelData::from(code);
+                    let nv = scaling.nanovolts(c);
+                    let float_nv = f64::from(scaling.microvolts(c)) * 1000.;
+                    assert!(
+                        (nv as f64 - float_nv).abs() <= lsb_nv,
+                        "{} {} {}",
+                        vref_uv,
+                        gain,
+                        code
+                    );
+
+                    assert_eq!(scaling.from_nanovolts(nv), c);
+                    let float_code: i32 = scaling.from_microvolts(nv as f32 / 1000.).into();
+                    assert!(
+                        (float_code - code).abs() <= 1,
+                        "{} {} {}",
+                        vref_uv,
+                        gain,
+                        code
+                    );
+                }
+            }
+        }
+
+        let scaling = Scaling::new(Reference::Internal4V033, GainSetting::G1).unwrap();
+        assert_eq!(
+            scaling.from_nanovolts(i64::MAX),
+            ChannelData::from(0x7F_FFFF)
+        );
+        assert_eq!(
+            scaling.from_nanovolts(i64::MIN),
+            ChannelData::from(-0x80_0000)
+        );
+    }
+
     #[test]
     fn bijective_i32() {
         const C1: i32 = 123;
@@ -0,0 +1,252 @@
+use embedded_hal::digital::InputPin;
+
+use crate::data::{self, RESYNC_ATTEMPTS};
+use crate::spi::Interface;
+use crate::{command, Ads129xx, Ads129xxError, Command, DataReady, NoPin, RegisterMap, Result};
+
+/// A driver whose data blocks can be read continuously with a `DataStream`
+pub trait Streaming: Ads129xx<<Self as Streaming>::Interface> {
+    /// Interface to the device
+    type Interface: Interface;
+    /// A decoded data block
+    type Frame;
+    /// A raw data block, as clocked out of the device
+    type Block: AsMut<[u8]> + Default;
+
+    /// Prepare the driver for decoding data blocks, before continuous reading starts
+    #[inline]
+    fn open_stream(&mut self) -> StreamResult<Self, ()> {
+        Ok(())
+    }
+
+    /// Decode a raw data block
+    fn frame(&self, block: Self::Block) -> Self::Frame;
+}
+
+type StreamResult<A, T> =
+    Result<T, <<A as Streaming>::Interface as Interface>::Error, StreamPinError<A>>;
+
+type StreamError<A> =
+    Ads129xxError<<<A as Streaming>::Interface as Interface>::Error, StreamPinError<A>>;
+
+type StreamPinError<A> = <A as Ads129xx<<A as Streaming>::Interface>>::PinError;
+
+/// Data stream. Used to read data continuously.
+///
+/// When created with `with_drdy`, the stream owns the nDRDY pin and `try_read` only reads a
+/// data block once the device signals that a new sample is ready.
+///
+/// Every data block is checked for the sync nibble of its status word. After an
+/// `Ads129xxError::FrameDesync`, call `resync` to restore frame alignment.
+pub struct DataStream<A, DRDY = NoPin>
+where
+    A: Streaming,
+{
+    ads: A,
+    drdy: DRDY,
+}
+
+impl<A> DataStream<A, NoPin>
+where
+    A: Streaming,
+{
+    /// Initialize stream, send RDATAC command
+    pub fn init(ads: A) -> StreamResult<A, Self> {
+        Self::open(ads, NoPin)
+    }
+
+    /// Send SDATAC command, then return the wrapped driver
+    pub fn into_inner(self) -> StreamResult<A, A> {
+        self.close().map(|(ads, NoPin)| ads)
+    }
+}
+
+impl<A, DRDY> DataStream<A, DRDY>
+where
+    A: Streaming,
+    DRDY: InputPin,
+    StreamPinError<A>: From<DRDY::Error>,
+{
+    /// Initialize stream reading on nDRDY, send RDATAC command
+    pub fn with_drdy(ads: A, drdy: DRDY) -> StreamResult<A, Self> {
+        Self::open(ads, drdy)
+    }
+
+    /// Read the next data block, or return `WouldBlock` if nDRDY does not signal a new one.
+    ///
+    /// nDRDY goes high again as soon as a data block is clocked out, so each sample is read
+    /// once, and never while the device is updating it. Like `read`, this skips the delays
+    /// around the transaction, and returns `WouldBlock` if the previous one was too recent.
+    pub fn try_read(&mut self) -> nb::Result<A::Frame, StreamError<A>> {
+        let ready = self
+            .drdy
+            .is_low()
+            .map_err(|e| Ads129xxError::Pin(e.into()))?;
+        if !ready {
+            return Err(nb::Error::WouldBlock);
+        }
+        let mut block = A::Block::default();
+        self.ads
+            .spi_device()
+            .fast_transfer(block.as_mut())
+            .map_err(|e| e.map(Ads129xxError::convert_pin))?;
+        data::check_sync(block.as_mut()[0])?;
+        Ok(self.ads.frame(block))
+    }
+
+    /// Send SDATAC command, then return the wrapped driver and the nDRDY pin
+    pub fn into_parts(self) -> StreamResult<A, (A, DRDY)> {
+        self.close()
+    }
+}
+
+impl<A, DRDY> DataStream<A, DRDY>
+where
+    A: Streaming,
+{
+    fn open(mut ads: A, drdy: DRDY) -> StreamResult<A, Self> {
+        ads.open_stream()?;
+        command(ads.spi_device(), Command::RDATAC)?;
+        Ok(Self { ads, drdy })
+    }
+
+    fn close(mut self) -> StreamResult<A, (A, DRDY)> {
+        self.ads.cmd(Command::SDATAC)?;
+        Ok((self.ads, self.drdy))
+    }
+
+    fn read_block(&mut self) -> StreamResult<A, A::Frame> {
+        let mut block = A::Block::default();
+        self.ads
+            .spi_device()
+            .transfer(block.as_mut())
+            .map_err(Ads129xxError::convert_pin)?;
+        data::check_sync(block.as_mut()[0])?;
+        Ok(self.ads.frame(block))
+    }
+
+    /// Read a single data block, skipping the delays around the transaction.
+    ///
+    /// If the previous transaction was too recent, this waits only for the remaining time. This
+    /// needs an interface that keeps track of time, like `spi::PacedInterface`; other interfaces
+    /// do a regular transfer.
+    pub fn read(&mut self) -> StreamResult<A, A::Frame> {
+        let mut block = A::Block::default();
+        nb::block!(self.ads.spi_device().fast_transfer(block.as_mut()))
+            .map_err(Ads129xxError::convert_pin)?;
+        data::check_sync(block.as_mut()[0])?;
+        Ok(self.ads.frame(block))
+    }
+
+    /// Restore frame alignment after a `FrameDesync` error.
+    ///
+    /// Restarts continuous reading with SDATAC and RDATAC, then reads data blocks until one
+    /// starts with the sync nibble again, and returns it. Gives up with `FrameDesync` if the
+    /// first few blocks are all out of alignment.
+    pub fn resync(&mut self) -> StreamResult<A, A::Frame> {
+        self.ads.cmd(Command::SDATAC)?;
+        command(self.ads.spi_device(), Command::RDATAC)?;
+        let mut status = 0;
+        for _ in 0..RESYNC_ATTEMPTS {
+            match self.read_block() {
+                Err(Ads129xxError::FrameDesync(s)) => status = s,
+                res => return res,
+            }
+        }
+        Err(Ads129xxError::FrameDesync(status))
+    }
+
+    /// The shadow copy of the register map, if the driver's register cache is enabled.
+    ///
+    /// The device ignores register reads while streaming, so this is the only way to inspect
+    /// the configuration until the stream is closed.
+    pub fn cached_registers(&self) -> Option<&RegisterMap> {
+        self.ads.cached_registers()
+    }
+}
+
+/// Without nDRDY, `next` reads a data block right away, so call it only once nDRDY has
+/// signalled a new sample. With nDRDY, `next` polls the pin and blocks until it does; use
+/// `try_read` to poll without blocking.
+impl<A, DRDY> Iterator for DataStream<A, DRDY>
+where
+    A: Streaming,
+    DRDY: DataReady<StreamPinError<A>>,
+{
+    type Item = StreamResult<A, A::Frame>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.drdy.is_ready() {
+                Some(Ok(false)) => (),
+                Some(Err(e)) => return Some(Err(Ads129xxError::Pin(e))),
+                Some(Ok(true)) | None => return Some(self.read_block()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ads1292::data::Ads1292Data;
+    use crate::data::Desync;
+    use crate::fake::{fake_ads1292, FakeDrdy, FakeState};
+    use crate::Ads129xxError;
+    use core::cell::Cell;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn try_read_waits_for_drdy() {
+        let state = FakeState::new();
+        let drdy = Cell::new(true);
+        let mut stream = fake_ads1292(&state)
+            .into_data_stream_with_drdy(FakeDrdy(&drdy))
+            .unwrap();
+        state.transactions();
+
+        match stream.try_read() {
+            Err(nb::Error::WouldBlock) => (),
+            _ => panic!("expected WouldBlock"),
+        }
+        assert_eq!(state.transactions(), 0);
+
+        drdy.set(false);
+        assert!(stream.try_read().is_ok());
+        assert_eq!(state.transactions(), 1);
+
+        // The iterator reads once nDRDY signals a sample
+        assert!(stream.next().unwrap().is_ok());
+        assert_eq!(state.transactions(), 1);
+    }
+
+    #[test]
+    fn resync_after_desync() {
+        let state = FakeState::new();
+        let mut stream = fake_ads1292(&state).into_data_stream().unwrap();
+        assert!(stream.read().unwrap().is_synced());
+
+        state.status.set(0x60);
+        match stream.next() {
+            Some(Err(Ads129xxError::FrameDesync(0x60))) => (),
+            _ => panic!("expected FrameDesync"),
+        }
+        match stream.resync() {
+            Err(Ads129xxError::FrameDesync(0x60)) => (),
+            _ => panic!("expected FrameDesync"),
+        }
+
+        state.status.set(0xC0);
+        state.transactions();
+        assert!(stream.resync().is_ok());
+        // SDATAC, RDATAC and one data block
+        assert_eq!(state.transactions(), 3);
+
+        // Blocks read with RDATA are checked as well
+        let mut ads = stream.into_inner().unwrap();
+        state.status.set(0x60);
+        match ads.read_data() {
+            Err(Ads129xxError::FrameDesync(0x60)) => (),
+            _ => panic!("expected FrameDesync"),
+        }
+        assert_eq!(Ads1292Data::try_from([0x60; 9]), Err(Desync(0x60)));
+    }
+}
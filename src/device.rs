@@ -0,0 +1,88 @@
+use crate::ads1291::Ads1291;
+use crate::ads1292::Ads1292;
+use crate::ads1292r::Ads1292R;
+use crate::spi::Interface;
+use crate::{Ads129xx, Ads129xxError, DeviceId, Model, Result};
+
+/// Any supported ADS129xx device, as detected from its ID register
+pub enum Device<DEV>
+where
+    DEV: Interface,
+{
+    Ads1291(Ads1291<DEV>),
+    Ads1292(Ads1292<DEV>),
+    Ads1292R(Ads1292R<DEV>),
+}
+
+impl<DEV> Device<DEV>
+where
+    DEV: Interface,
+{
+    /// Read the ID register of the connected device and initialize the matching driver.
+    ///
+    /// Fails with `Ads129xxError::UnexpectedId` if the ID is invalid, or belongs to one of the
+    /// 16-bit ADS1191 and ADS1192 parts, which are not supported.
+    pub fn init(spi: DEV) -> Result<Self, DEV::Error, DEV::PinError> {
+        let mut probe = Ads1292::new(spi)?;
+        let id = probe.read_device_id()?;
+        let spi = probe.into_spi_device();
+
+        // The device is in command mode and its ID known, so neither is repeated
+        match id.model() {
+            Some(Model::Ads1291) => Ok(Device::Ads1291(Ads1291::with_id(spi, id)?)),
+            Some(Model::Ads1292) => Ok(Device::Ads1292(Ads1292::with_id(spi, id)?)),
+            Some(Model::Ads1292R) => Ok(Device::Ads1292R(Ads1292R::with_id(spi, id)?)),
+            _ => Err(Ads129xxError::UnexpectedId(id.0)),
+        }
+    }
+
+    /// The model of the detected device
+    pub fn model(&self) -> Model {
+        match self {
+            Device::Ads1291(_) => Model::Ads1291,
+            Device::Ads1292(_) => Model::Ads1292,
+            Device::Ads1292R(_) => Model::Ads1292R,
+        }
+    }
+
+    /// Read and decode the ID register of the detected device
+    pub fn device_id(&mut self) -> Result<DeviceId, DEV::Error, DEV::PinError> {
+        match self {
+            Device::Ads1291(ads) => ads.read_device_id(),
+            Device::Ads1292(ads) => ads.read_device_id(),
+            Device::Ads1292R(ads) => ads.read_device_id(),
+        }
+    }
+
+    /// Consume self and return the wrapped SpiDevice
+    pub fn into_spi_device(self) -> DEV {
+        match self {
+            Device::Ads1291(ads) => ads.into_spi_device(),
+            Device::Ads1292(ads) => ads.into_spi_device(),
+            Device::Ads1292R(ads) => ads.into_spi_device(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{FakeAds, FakeState};
+    use crate::Register;
+
+    #[test]
+    fn device_init_reads_id_once() {
+        let state = FakeState::new();
+        state.regs.borrow_mut()[Register::ID] = 0x73;
+        state.regs.borrow_mut()[Register::RESP1] = 0xC2;
+        let mut device = Device::init(FakeAds(&state)).unwrap();
+        // SDATAC, the ID register and RESP1
+        assert_eq!(state.transactions(), 3);
+        assert_eq!(device.model(), Model::Ads1292R);
+        match device {
+            Device::Ads1292R(ref ads) => assert!(ads.respiration_enabled()),
+            _ => panic!("expected an ADS1292R"),
+        }
+        assert_eq!(device.device_id().unwrap().model(), Some(Model::Ads1292R));
+    }
+}
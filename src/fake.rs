@@ -0,0 +1,161 @@
+//! An emulated ADS1292 and pins for the tests
+
+use core::cell::{Cell, RefCell};
+use core::convert::Infallible;
+
+use crate::ads1292::Ads1292;
+use crate::data::ChannelData;
+use crate::spi::Interface;
+use crate::{Command, Register, RegisterMap, Result};
+
+/// Register file of an emulated ADS1292
+pub(crate) struct FakeState {
+    pub(crate) regs: RefCell<RegisterMap>,
+    pub(crate) transactions: Cell<usize>,
+    pub(crate) ignore_writes: Cell<bool>,
+    /// First byte of the data blocks
+    pub(crate) status: Cell<u8>,
+    /// Code of both channels in the data blocks
+    pub(crate) sample: Cell<i32>,
+}
+
+impl FakeState {
+    pub(crate) fn new() -> Self {
+        FakeState {
+            regs: RefCell::new(RegisterMap([
+                0x53, 0x02, 0x80, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x0C,
+            ])),
+            transactions: Cell::new(0),
+            ignore_writes: Cell::new(false),
+            status: Cell::new(0xC0),
+            sample: Cell::new(0),
+        }
+    }
+
+    /// Number of transactions since the last call
+    pub(crate) fn transactions(&self) -> usize {
+        self.transactions.replace(0)
+    }
+}
+
+/// Emulates an ADS1292 behind the SPI interface
+pub(crate) struct FakeAds<'a>(pub(crate) &'a FakeState);
+
+impl<'a> Interface for FakeAds<'a> {
+    type Error = Infallible;
+    type PinError = Infallible;
+
+    fn transfer(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        self.0.transactions.set(self.0.transactions.get() + 1);
+        if words[0] & 0xE0 == Command::RREG.word() {
+            let start = (words[0] & 0x1F) as usize;
+            let n = words[1] as usize + 1;
+            words[2..2 + n].copy_from_slice(&self.0.regs.borrow().0[start..start + n]);
+        } else if words.len() == 6 || words.len() == 9 {
+            // A data block of an ADS1291 or an ADS1292, with the sample on every channel
+            words[0] = self.0.status.get();
+            let ChannelData(b0, b1, b2) = self.0.sample.get().into();
+            for chan in words[3..].chunks_mut(3) {
+                chan.copy_from_slice(&[b0, b1, b2]);
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        self.0.transactions.set(self.0.transactions.get() + 1);
+        if words[0] & 0xE0 == Command::WREG.word() && !self.0.ignore_writes.get() {
+            let start = (words[0] & 0x1F) as usize;
+            let n = words[1] as usize + 1;
+            self.0.regs.borrow_mut().0[start..start + n].copy_from_slice(&words[2..2 + n]);
+        } else if words == [Command::RESET.word()] {
+            let mut regs = self.0.regs.borrow_mut();
+            let id = regs[Register::ID];
+            *regs = FakeState::new().regs.into_inner();
+            regs[Register::ID] = id;
+        }
+        Ok(())
+    }
+
+    fn wait(&mut self, _i: u16) {}
+}
+
+/// Error of a pin that cannot be driven
+#[derive(Debug, PartialEq)]
+pub(crate) struct PinFault;
+
+impl From<Infallible> for PinFault {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+impl embedded_hal::digital::Error for PinFault {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+/// An output pin that fails to be driven
+pub(crate) struct BrokenPin;
+
+impl embedded_hal::digital::ErrorType for BrokenPin {
+    type Error = PinFault;
+}
+
+impl embedded_hal::digital::OutputPin for BrokenPin {
+    fn set_low(&mut self) -> core::result::Result<(), PinFault> {
+        Err(PinFault)
+    }
+
+    fn set_high(&mut self) -> core::result::Result<(), PinFault> {
+        Err(PinFault)
+    }
+}
+
+/// A nDRDY pin whose level is set by the test
+pub(crate) struct FakeDrdy<'a>(pub(crate) &'a Cell<bool>);
+
+impl embedded_hal::digital::ErrorType for FakeDrdy<'_> {
+    type Error = Infallible;
+}
+
+impl embedded_hal::digital::InputPin for FakeDrdy<'_> {
+    fn is_high(&mut self) -> core::result::Result<bool, Infallible> {
+        Ok(self.0.get())
+    }
+
+    fn is_low(&mut self) -> core::result::Result<bool, Infallible> {
+        Ok(!self.0.get())
+    }
+}
+
+/// An output pin that records its level and how often it was driven low
+pub(crate) struct FakePin<'a> {
+    pub(crate) high: &'a Cell<bool>,
+    pub(crate) pulses: &'a Cell<usize>,
+}
+
+impl embedded_hal::digital::ErrorType for FakePin<'_> {
+    type Error = Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for FakePin<'_> {
+    fn set_low(&mut self) -> core::result::Result<(), Infallible> {
+        self.high.set(false);
+        self.pulses.set(self.pulses.get() + 1);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> core::result::Result<(), Infallible> {
+        self.high.set(true);
+        Ok(())
+    }
+}
+
+/// An ADS1292 driver on a fresh emulated device, with the transaction count reset
+pub(crate) fn fake_ads1292(state: &FakeState) -> Ads1292<FakeAds<'_>> {
+    let ads = Ads1292::new(FakeAds(state)).unwrap();
+    state.transactions();
+    ads
+}
@@ -0,0 +1,226 @@
+//! The ADS129xx has two general-purpose I/O pins, controlled through the GPIO register. The
+//! `Gpio1` and `Gpio2` handles expose them as embedded-hal pins, so they can be handed to code
+//! that expects an `OutputPin` or `InputPin`. With the `eh02` feature, they implement the
+//! embedded-hal 0.2 pin traits as well.
+//!
+//! Both handles share the driver through a `RefCell`, and each pin operation is a
+//! read-modify-write of the GPIO register. Driving a pin configures it as an output; reading a
+//! pin does not change its direction, use `into_input` for that.
+//!
+//! `split` borrows the `RefCell` mutably, so there is only ever one handle per pin. Once both
+//! handles are dropped, the driver can be used directly again.
+//!
+//! ```norun
+//!let mut ads = RefCell::new(ads1292);
+//!let (mut led, button) = split(&mut ads);
+//!let mut button = button.into_input()?;
+//!
+//!if button.is_high()? {
+//!    led.set_high()?;
+//!}
+//!```
+
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+
+use crate::spi::Interface;
+use crate::{Ads129xx, Ads129xxError, GpioConfig, Result};
+
+/// Both GPIO pin handles, as returned by `split`
+pub type GpioPins<'a, A, DEV> = (Gpio1<'a, A, DEV>, Gpio2<'a, A, DEV>);
+
+/// Split the GPIO pins of a shared driver into separate handles.
+pub fn split<A, DEV>(ads: &mut RefCell<A>) -> GpioPins<'_, A, DEV>
+where
+    A: Ads129xx<DEV>,
+    DEV: Interface,
+{
+    let ads = &*ads;
+    (Gpio1::new(ads), Gpio2::new(ads))
+}
+
+macro_rules! gpio_pin {
+    ($name:ident, $doc:expr, $control:ident, $set_control:ident, $data:ident, $set_data:ident) => {
+        #[doc = $doc]
+        pub struct $name<'a, A, DEV> {
+            ads: &'a RefCell<A>,
+            _interface: PhantomData<DEV>,
+        }
+
+        impl<'a, A, DEV> $name<'a, A, DEV>
+        where
+            A: Ads129xx<DEV>,
+            DEV: Interface,
+        {
+            /// Create a handle for this pin. Does not touch the pin configuration.
+            pub(crate) fn new(ads: &'a RefCell<A>) -> Self {
+                Self {
+                    ads,
+                    _interface: PhantomData,
+                }
+            }
+
+            /// Configure the pin as an input.
+            pub fn into_input(self) -> Result<Self, DEV::Error, A::PinError> {
+                self.modify(|gpio| gpio.$set_control(true))?;
+                Ok(self)
+            }
+
+            /// Configure the pin as an output, driving the current level of its data bit.
+            pub fn into_output(self) -> Result<Self, DEV::Error, A::PinError> {
+                self.modify(|gpio| gpio.$set_control(false))?;
+                Ok(self)
+            }
+
+            /// Whether the pin is currently configured as an input.
+            pub fn is_input(&self) -> Result<bool, DEV::Error, A::PinError> {
+                Ok(self.ads.borrow_mut().read_gpio()?.$control())
+            }
+
+            fn modify<F: FnOnce(&mut GpioConfig)>(
+                &self,
+                f: F,
+            ) -> Result<(), DEV::Error, A::PinError> {
+                self.ads.borrow_mut().modify_gpio(f)
+            }
+
+            fn drive(&self, high: bool) -> Result<(), DEV::Error, A::PinError> {
+                self.modify(|gpio| {
+                    gpio.$set_control(false);
+                    gpio.$set_data(high);
+                })
+            }
+
+            fn level(&self) -> Result<bool, DEV::Error, A::PinError> {
+                Ok(self.ads.borrow_mut().read_gpio()?.$data())
+            }
+        }
+
+        impl<'a, A, DEV> ErrorType for $name<'a, A, DEV>
+        where
+            A: Ads129xx<DEV>,
+            DEV: Interface,
+            DEV::Error: core::fmt::Debug,
+            A::PinError: core::fmt::Debug,
+        {
+            type Error = Ads129xxError<DEV::Error, A::PinError>;
+        }
+
+        impl<'a, A, DEV> OutputPin for $name<'a, A, DEV>
+        where
+            A: Ads129xx<DEV>,
+            DEV: Interface,
+            DEV::Error: core::fmt::Debug,
+            A::PinError: core::fmt::Debug,
+        {
+            fn set_low(&mut self) -> Result<(), DEV::Error, A::PinError> {
+                self.drive(false)
+            }
+
+            fn set_high(&mut self) -> Result<(), DEV::Error, A::PinError> {
+                self.drive(true)
+            }
+        }
+
+        impl<'a, A, DEV> InputPin for $name<'a, A, DEV>
+        where
+            A: Ads129xx<DEV>,
+            DEV: Interface,
+            DEV::Error: core::fmt::Debug,
+            A::PinError: core::fmt::Debug,
+        {
+            fn is_high(&mut self) -> Result<bool, DEV::Error, A::PinError> {
+                self.level()
+            }
+
+            fn is_low(&mut self) -> Result<bool, DEV::Error, A::PinError> {
+                self.level().map(|high| !high)
+            }
+        }
+
+        #[cfg(feature = "eh02")]
+        impl<'a, A, DEV> embedded_hal_02::digital::v2::OutputPin for $name<'a, A, DEV>
+        where
+            A: Ads129xx<DEV>,
+            DEV: Interface,
+        {
+            type Error = Ads129xxError<DEV::Error, A::PinError>;
+
+            fn set_low(&mut self) -> Result<(), DEV::Error, A::PinError> {
+                self.drive(false)
+            }
+
+            fn set_high(&mut self) -> Result<(), DEV::Error, A::PinError> {
+                self.drive(true)
+            }
+        }
+
+        #[cfg(feature = "eh02")]
+        impl<'a, A, DEV> embedded_hal_02::digital::v2::InputPin for $name<'a, A, DEV>
+        where
+            A: Ads129xx<DEV>,
+            DEV: Interface,
+        {
+            type Error = Ads129xxError<DEV::Error, A::PinError>;
+
+            fn is_high(&self) -> Result<bool, DEV::Error, A::PinError> {
+                self.level()
+            }
+
+            fn is_low(&self) -> Result<bool, DEV::Error, A::PinError> {
+                self.level().map(|high| !high)
+            }
+        }
+    };
+}
+
+gpio_pin!(
+    Gpio1,
+    "Handle to the GPIO1 pin of an ADS129xx",
+    gpio_c_1,
+    set_gpio_c_1,
+    gpio_d_1,
+    set_gpio_d_1
+);
+gpio_pin!(
+    Gpio2,
+    "Handle to the GPIO2 pin of an ADS129xx",
+    gpio_c_2,
+    set_gpio_c_2,
+    gpio_d_2,
+    set_gpio_d_2
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{fake_ads1292, FakeState};
+    use crate::Register;
+
+    #[test]
+    fn gpio_pins() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state);
+        let gpio = ads.read_gpio().unwrap();
+        assert!(gpio.gpio_c_1() && gpio.gpio_c_2());
+
+        let mut ads = RefCell::new(ads);
+        let (mut led, button) = split(&mut ads);
+        led.set_high().unwrap();
+        assert_eq!(state.regs.borrow()[Register::GPIO], 0x09);
+        assert!(!led.is_input().unwrap());
+
+        let mut button = button.into_input().unwrap();
+        assert!(button.is_low().unwrap());
+        state.regs.borrow_mut()[Register::GPIO] |= 0x02;
+        assert!(button.is_high().unwrap());
+        assert!(led.is_high().unwrap());
+        led.set_low().unwrap();
+        assert!(led.is_low().unwrap());
+
+        // Once the handles are no longer used, the driver is available again
+        assert_eq!(ads.get_mut().read_gpio().unwrap().0, 0x0A);
+    }
+}
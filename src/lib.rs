@@ -3,59 +3,88 @@
 //!
 //! Driver crate for the Texas Instruments ADS1292 24-bit 2-channel low-power analog front end for ECG applications.
 //!
-//! This initial version supports the ADS1292 (for the most part), but it's a goal to support the ADS1291 and ADS1292R as well.
+//! Supports the ADS1292 (for the most part), the single-channel ADS1291 and the ADS1292R with its respiration front-end.
 //!
 //! Usage:
 //!
 //! ```norun
-//!// spi: spi interface
-//!// ncs: not-Chip-Select pin
-//!// timer: timer, 500kHz timeout.
+//!// spi: embedded-hal 1.0 SpiDevice, using ads129xx::spi::MODE
+//!// delay: embedded-hal 1.0 DelayNs
+//!// drdy: embedded-hal 1.0 InputPin, connected to nDRDY
 //!
-//!let spi_device = SpiDevice::new(spi, ncs, timer)?;
-//!let mut ads = Ads1292::init(spi_device)?;
+//!let spi_device = SpiInterface::new(spi, delay);
+//!let mut ads = Ads1292::new(spi_device)?; // sends SDATAC, so the registers can be accessed
+//!ads.init()?;
 //!
 //!// start conversions
-//!ads.cmd(ads129xx::Command::START).unwrap();
-//!ads.wait(200)?; // Wait a while in between sending commands
-//!ads.cmd(ads129xx::Command::RDATAC).unwrap();
-//!ads.wait(200)?;
-//!
-//!let mut stream = ads.into_data_stream()?;
-//!
-//!let mut buf = [[ChannelData::default(); 2]; 10];
+//!ads.start()?;
+//!ads.wait(200); // Wait a while in between sending commands
 //!
 //!// Opens stream, sends RDATAC command to ads
-//!let data_stream = ads1292.into_data_stream()?;
-//!ads.wait(200)?;
+//!let mut data_stream = ads.into_data_stream()?;
 //!
 //!// A buffer to read data into
-//!let mut buf = [[Ads1292Data::default(); 2]; 2000];
+//!let mut buf = [Ads1292Data::default(); 2000];
 //!
 //!for i in buf.iter_mut() {
 //!    // some way of finding out NDRDY has been low since last read (preferably by an interrupt-set flag)
-//    while !data_ready() {}
-//!    // data_stream always returns data (for now), so we can unwrap here
+//!    while !data_ready() {}
+//!    // next never returns None; it reads the current sample right away, as the stream does not own nDRDY
 //!    *i = data_stream.next().unwrap()?;
 //!}
+//!
 //!// Don't forget to close; this will send the SDATAC command to the ads
-//!data_stream.into_inner();
+//!let ads = data_stream.into_inner()?;
+//!
+//!// With the nDRDY pin, next blocks until a new sample is ready, and try_read returns
+//!// WouldBlock instead
+//!let mut data_stream = ads.into_data_stream_with_drdy(drdy)?;
+//!let data = data_stream.next().unwrap()?;
+//!match data_stream.try_read() {
+//!    Ok(data) => { /* a new sample */ }
+//!    Err(nb::Error::WouldBlock) => { /* no new sample yet */ }
+//!    Err(nb::Error::Other(e)) => return Err(e),
+//!}
+//!let (ads, drdy) = data_stream.into_parts()?;
 //!```
 //!
+//! With the `eh02` feature, enabled by default, `spi::SpiDevice` provides the same interface on top of
+//! embedded-hal 0.2 SPI, a chip select pin and a `CountDown` timer. With the `async` feature, the
+//! `asynch` module provides an ADS1292 driver on embedded-hal-async.
+//!
 
-use embedded_hal::blocking::spi as bspi;
-use embedded_hal::digital::v2::OutputPin;
-use embedded_hal::timer::CountDown;
-
-use crate::spi::SpiDevice;
+use crate::config::{Ads1292Config, ConfigDiff};
+use crate::data::{LeadOffStatus, Scaling};
+use crate::mode::Standby;
+use crate::spi::Interface;
 
+/// Ads1291-specific code
+pub mod ads1291;
 /// Ads1292-specific code
 pub mod ads1292;
+/// Ads1292R-specific code
+pub mod ads1292r;
+/// Async drivers
+#[cfg(feature = "async")]
+pub mod asynch;
+/// Whole-device configuration snapshots
+pub mod config;
 /// Data representation
 pub mod data;
+/// Continuous data reading
+pub mod data_stream;
+/// Device auto-detection
+pub mod device;
+#[cfg(test)]
+mod fake;
+/// General-purpose I/O pins
+pub mod gpio;
+/// Operating modes
+pub mod mode;
 mod register;
 /// SPI interface
 pub mod spi;
+#[cfg(feature = "eh02")]
 mod util;
 
 pub use register::*;
@@ -64,7 +93,7 @@ pub use register::*;
 ///
 /// Table 13 page 35 of specification.
 #[allow(non_camel_case_types)]
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Command {
     /// Wake-up from standby mode
     WAKEUP = 0x02,
@@ -97,53 +126,386 @@ impl Command {
     pub fn word(self) -> u8 {
         self as u8
     }
+
+    /// Whether `Ads129xx::cmd` refuses to send this command.
+    ///
+    /// These change the operating mode or start and stop conversions, which the drivers model
+    /// as types and keep track of, or they need operands, like RREG and WREG. Use the driver
+    /// methods for them instead, like `standby`, `reset`, `into_data_stream` or `read_register`.
+    pub fn is_restricted(self) -> bool {
+        !matches!(self, Command::OFFSETCAL | Command::SDATAC | Command::RDATA)
+    }
+}
+
+/// Device clock periods the device needs after a reset before it accepts commands
+pub(crate) const RESET_RECOVERY_TCLK: u16 = 18;
+
+/// Send a command, including the ones `Ads129xx::cmd` refuses
+#[inline]
+pub(crate) fn command<DEV, PinE>(spi: &mut DEV, cmd: Command) -> Result<(), DEV::Error, PinE>
+where
+    DEV: Interface,
+    PinE: From<DEV::PinError>,
+{
+    spi.write(&[cmd.word()]).map_err(Ads129xxError::convert_pin)
 }
 
 #[derive(Debug, Copy, Clone)]
-pub enum Ads129xxError<E> {
-    BootFailure,
+pub enum Ads129xxError<E, PinE = core::convert::Infallible> {
+    /// The ID register did not identify a supported device; carries the ID byte that was read
+    UnexpectedId(u8),
+    /// A burst register access would run past the last register
+    InvalidRegisterRange,
+    /// A write to a register that cannot be written, like the ID register
+    ReadOnlyRegister(Register),
+    /// A register did not read back the value that was written to it
+    RegisterMismatch { reg: Register, wrote: u8, read: u8 },
     /// SPI bus error
     SpiError(E),
+    /// Error driving a pin, like the chip select
+    Pin(PinE),
+    /// The registers do not determine how to convert data to volts, as the reference comes
+    /// from VREFP or the gain is reserved
+    UnknownScaling,
+    /// A data block did not start with the sync nibble of the status word, so the stream lost
+    /// frame alignment; carries the first byte that was read
+    FrameDesync(u8),
+    /// The command is only sent by the driver methods that model its effect, see
+    /// `Command::is_restricted`
+    RestrictedCommand(Command),
+    /// A measurement read a full-scale code, so the input exceeded the range of the reference
+    InputSaturated,
 }
 
-impl<E> From<E> for Ads129xxError<E> {
+impl<E, PinE> From<E> for Ads129xxError<E, PinE> {
     fn from(e: E) -> Self {
         Ads129xxError::SpiError(e)
     }
 }
 
-pub type Result<T, E> = core::result::Result<T, Ads129xxError<E>>;
+impl<E, PinE> Ads129xxError<E, PinE> {
+    /// Convert the pin error, like from the pin error type of the interface into the driver's
+    pub(crate) fn convert_pin<P: From<PinE>>(self) -> Ads129xxError<E, P> {
+        match self {
+            Ads129xxError::UnexpectedId(id) => Ads129xxError::UnexpectedId(id),
+            Ads129xxError::InvalidRegisterRange => Ads129xxError::InvalidRegisterRange,
+            Ads129xxError::ReadOnlyRegister(reg) => Ads129xxError::ReadOnlyRegister(reg),
+            Ads129xxError::RegisterMismatch { reg, wrote, read } => {
+                Ads129xxError::RegisterMismatch { reg, wrote, read }
+            }
+            Ads129xxError::SpiError(e) => Ads129xxError::SpiError(e),
+            Ads129xxError::Pin(e) => Ads129xxError::Pin(e.into()),
+            Ads129xxError::UnknownScaling => Ads129xxError::UnknownScaling,
+            Ads129xxError::FrameDesync(status) => Ads129xxError::FrameDesync(status),
+            Ads129xxError::RestrictedCommand(cmd) => Ads129xxError::RestrictedCommand(cmd),
+            Ads129xxError::InputSaturated => Ads129xxError::InputSaturated,
+        }
+    }
+}
+
+impl<E, PinE> embedded_hal::digital::Error for Ads129xxError<E, PinE>
+where
+    E: core::fmt::Debug,
+    PinE: core::fmt::Debug,
+{
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+pub type Result<T, E, PinE = core::convert::Infallible> =
+    core::result::Result<T, Ads129xxError<E, PinE>>;
+
+/// Compare the bits of written register values that should read back unchanged.
+fn check_readback<E, PinE>(start: Register, wrote: &[u8], read: &[u8]) -> Result<(), E, PinE> {
+    for ((&reg, &wrote), &read) in Register::ALL[start.addr() as usize..]
+        .iter()
+        .zip(wrote)
+        .zip(read)
+    {
+        let mask = reg.readback_mask(wrote);
+        if wrote & mask != read & mask {
+            return Err(Ads129xxError::RegisterMismatch { reg, wrote, read });
+        }
+    }
+    Ok(())
+}
+
+/// A RREG or WREG transaction, built and checked apart from the bus so that the blocking and
+/// the async drivers share it.
+struct RegisterFrame {
+    buf: [u8; 2 + REGISTER_COUNT],
+    len: usize,
+}
+
+impl RegisterFrame {
+    fn new<E, PinE>(cmd: Command, start: Register, n: usize) -> Result<Self, E, PinE> {
+        if n == 0 || start.addr() as usize + n > REGISTER_COUNT {
+            return Err(Ads129xxError::InvalidRegisterRange);
+        }
+        let mut buf = [0u8; 2 + REGISTER_COUNT];
+        buf[0] = cmd.word() | start.addr();
+        buf[1] = (n - 1) as u8; // n registers, but subtract 1
+        Ok(RegisterFrame { buf, len: 2 + n })
+    }
+
+    /// Read `n` registers starting at `start`; `n` must not be 0
+    fn read<E, PinE>(start: Register, n: usize) -> Result<Self, E, PinE> {
+        Self::new(Command::RREG, start, n)
+    }
+
+    /// Write `data` to the registers starting at `start`, which must not be empty
+    fn write<E, PinE>(start: Register, data: &[u8]) -> Result<Self, E, PinE> {
+        let mut frame = Self::new(Command::WREG, start, data.len())?;
+        if !start.writable() {
+            return Err(Ads129xxError::ReadOnlyRegister(start));
+        }
+        frame.buf[2..frame.len].copy_from_slice(data);
+        Ok(frame)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[..self.len]
+    }
+
+    /// The register values, after the transaction
+    fn data(&self) -> &[u8] {
+        &self.buf[2..self.len]
+    }
+}
+
+/// Register bookkeeping kept by every driver: write verification and the shadow cache.
+#[derive(Debug, Default, Clone)]
+pub struct RegisterState {
+    verify: bool,
+    cache: Option<RegisterMap>,
+}
+
+impl RegisterState {
+    /// The value of `reg`, if the cache is enabled and can answer for it
+    fn cached(&self, reg: Register) -> Option<u8> {
+        match &self.cache {
+            Some(cache) if reg.cacheable() => Some(cache[reg]),
+            _ => None,
+        }
+    }
+
+    /// Record the values that were read from or written to the registers from `start` on
+    fn record(&mut self, start: Register, data: &[u8]) {
+        if let Some(cache) = self.cache.as_mut() {
+            let start = start.addr() as usize;
+            cache.0[start..start + data.len()].copy_from_slice(data);
+        }
+    }
+}
+
+/// Stands in for a control pin that is not connected to the MCU.
+pub struct NoPin;
+
+/// A control pin of the device, like START or PWDN/RESET: either an `OutputPin` that is
+/// connected to the MCU, or `NoPin`. Pin errors are converted into `PinE`.
+pub trait ControlPin<PinE> {
+    /// Drive the pin high or low; returns `None` if it is not connected.
+    fn set_level(&mut self, high: bool) -> Option<core::result::Result<(), PinE>>;
+}
+
+impl<PinE> ControlPin<PinE> for NoPin {
+    #[inline]
+    fn set_level(&mut self, _high: bool) -> Option<core::result::Result<(), PinE>> {
+        None
+    }
+}
+
+impl<P, PinE> ControlPin<PinE> for P
+where
+    P: embedded_hal::digital::OutputPin,
+    PinE: From<P::Error>,
+{
+    #[inline]
+    fn set_level(&mut self, high: bool) -> Option<core::result::Result<(), PinE>> {
+        let res = if high {
+            self.set_high()
+        } else {
+            self.set_low()
+        };
+        Some(res.map_err(PinE::from))
+    }
+}
+
+/// The nDRDY pin of the device: an `InputPin` that is connected to the MCU, or `NoPin`. Pin
+/// errors are converted into `PinE`.
+pub trait DataReady<PinE> {
+    /// Whether nDRDY signals a new sample; returns `None` if it is not connected.
+    fn is_ready(&mut self) -> Option<core::result::Result<bool, PinE>>;
+}
+
+impl<PinE> DataReady<PinE> for NoPin {
+    #[inline]
+    fn is_ready(&mut self) -> Option<core::result::Result<bool, PinE>> {
+        None
+    }
+}
+
+impl<P, PinE> DataReady<PinE> for P
+where
+    P: embedded_hal::digital::InputPin,
+    PinE: From<P::Error>,
+{
+    #[inline]
+    fn is_ready(&mut self) -> Option<core::result::Result<bool, PinE>> {
+        Some(self.is_low().map_err(PinE::from))
+    }
+}
 
 macro_rules! simple_register {
-    ($read_name:ident, $write_name:ident, $register:ident, $valuetype:ident) => {
+    ($read_name:ident, $write_name:ident, $modify_name:ident, $register:ident, $valuetype:ident) => {
         #[inline]
-        fn $read_name(&mut self) -> Result<$valuetype, E> {
+        fn $read_name(&mut self) -> Result<$valuetype, DEV::Error, Self::PinError> {
             Ok($valuetype(self.read_register(Register::$register)?))
         }
         #[inline]
-        fn $write_name(&mut self, value: &$valuetype) -> Result<(), E> {
+        fn $write_name(&mut self, value: &$valuetype) -> Result<(), DEV::Error, Self::PinError> {
             self.write_register(Register::$register, value.0)
         }
+        /// Read the register, let `f` modify it, and write it back if it changed
+        #[inline]
+        fn $modify_name<F: FnOnce(&mut $valuetype)>(
+            &mut self,
+            f: F,
+        ) -> Result<(), DEV::Error, Self::PinError> {
+            let old = self.$read_name()?;
+            let mut new = old;
+            f(&mut new);
+            if new != old {
+                self.$write_name(&new)?;
+            }
+            Ok(())
+        }
+    };
+}
+
+/// Wait for the device to come out of reset, return it to command mode and bring the driver's
+/// view of the registers up to date.
+pub(crate) fn recover_from_reset<DEV, A>(ads: &mut A) -> Result<(), DEV::Error, A::PinError>
+where
+    DEV: Interface,
+    A: Ads129xx<DEV> + ?Sized,
+{
+    ads.spi_device().wait_tclk(RESET_RECOVERY_TCLK);
+    command(ads.spi_device(), Command::SDATAC)?;
+    ads.registers_reset();
+    if ads.cached_registers().is_some() {
+        ads.enable_register_cache()?;
     }
+    Ok(())
 }
 
 /// Represents any ADS129xx device
-pub trait Ads129xx<SPI, NCS, TIM, E>
+pub trait Ads129xx<DEV>
 where
-    SPI: bspi::Transfer<u8, Error = E> + bspi::Write<u8, Error = E>,
-    NCS: OutputPin<Error = core::convert::Infallible>,
-    TIM: CountDown,
+    DEV: Interface,
 {
+    /// Error of the pins: the chip select of the interface, and the control pins the driver
+    /// owns, like nDRDY or START
+    type PinError: From<DEV::PinError>;
+
     /// Get a mutable reference to the wrapped SpiDevice
-    fn spi_device(&mut self) -> &mut SpiDevice<SPI, NCS, TIM>;
+    fn spi_device(&mut self) -> &mut DEV;
 
     /// Consume self and return the wrapped SpiDevice
-    fn into_spi_device(self) -> SpiDevice<SPI, NCS, TIM>;
+    fn into_spi_device(self) -> DEV;
+
+    /// Get a reference to the register bookkeeping of the driver
+    fn register_state(&self) -> &RegisterState;
+
+    /// Get a mutable reference to the register bookkeeping of the driver
+    fn register_state_mut(&mut self) -> &mut RegisterState;
+
+    /// Called after `data` was written to the registers from `start` on, by every register
+    /// write path. Drivers override it to keep state that depends on register values in sync.
+    #[inline]
+    fn registers_written(&mut self, _start: Register, _data: &[u8]) {}
 
-    /// Send a command to the ADS129xx
+    /// Called after a reset returned the registers to their default values. Drivers override
+    /// it to reset state that mirrors the device.
     #[inline]
-    fn cmd(&mut self, cmd: Command) -> Result<(), E> {
-        self.spi_device().write(&[cmd.word()]).map_err(Into::into)
+    fn registers_reset(&mut self) {}
+
+    /// Whether register writes are read back and verified
+    #[inline]
+    fn verify_writes(&self) -> bool {
+        self.register_state().verify
+    }
+
+    /// Enable or disable verification of register writes.
+    ///
+    /// When enabled, every register write is followed by a read-back, and a differing value
+    /// results in `Ads129xxError::RegisterMismatch`. This catches writes that were ignored,
+    /// for instance because the device was in RDATAC mode.
+    #[inline]
+    fn set_verify_writes(&mut self, verify: bool) {
+        self.register_state_mut().verify = verify;
+    }
+
+    /// Read the whole register map into a shadow cache, and keep it up to date from then on.
+    ///
+    /// While the cache is enabled, register reads are answered from the cache instead of the
+    /// bus, except for LOFF_STAT and GPIO which reflect live pin states, and the read-only ID
+    /// register. Commands that change registers behind the driver's back make the cache stale;
+    /// the driver methods that reset the device reload it.
+    fn enable_register_cache(&mut self) -> Result<(), DEV::Error, Self::PinError> {
+        let map = self.dump_registers()?;
+        self.register_state_mut().cache = Some(map);
+        Ok(())
+    }
+
+    /// Stop caching registers; all reads go to the bus again.
+    #[inline]
+    fn disable_register_cache(&mut self) {
+        self.register_state_mut().cache = None;
+    }
+
+    /// The shadow copy of the register map, if the cache is enabled
+    #[inline]
+    fn cached_registers(&self) -> Option<&RegisterMap> {
+        self.register_state().cache.as_ref()
+    }
+
+    /// Send a command to the ADS129xx.
+    ///
+    /// Fails with `Ads129xxError::RestrictedCommand` for commands that the driver methods
+    /// send, see `Command::is_restricted`.
+    #[inline]
+    fn cmd(&mut self, cmd: Command) -> Result<(), DEV::Error, Self::PinError> {
+        if cmd.is_restricted() {
+            return Err(Ads129xxError::RestrictedCommand(cmd));
+        }
+        command(self.spi_device(), cmd)
+    }
+
+    /// Check whether the interface can start the next transaction without blocking.
+    ///
+    /// With a `spi::PacedInterface`, this returns `WouldBlock` until the device has had time to
+    /// process the previous transaction.
+    #[inline]
+    fn poll_ready(&mut self) -> nb::Result<(), Ads129xxError<DEV::Error, Self::PinError>> {
+        self.spi_device()
+            .poll_ready()
+            .map_err(|e| e.map(Ads129xxError::convert_pin))
+    }
+
+    /// Send a command to the ADS129xx if the interface is ready, or return `WouldBlock`
+    #[inline]
+    fn try_cmd(
+        &mut self,
+        cmd: Command,
+    ) -> nb::Result<(), Ads129xxError<DEV::Error, Self::PinError>> {
+        self.poll_ready()?;
+        Ok(self.cmd(cmd)?)
     }
 
     #[inline]
@@ -151,30 +513,309 @@ where
         self.spi_device().wait(i)
     }
 
+    /// Reset the device with the RESET command, and wait until it accepts commands again.
+    ///
+    /// The device comes out of reset in RDATAC mode, so this sends SDATAC to return to command
+    /// mode. All registers have their default values afterwards; if the register cache is
+    /// enabled, it is reloaded.
+    fn reset(&mut self) -> Result<(), DEV::Error, Self::PinError> {
+        command(self.spi_device(), Command::RESET)?;
+        recover_from_reset(self)
+    }
+
+    /// Send the STANDBY command, and return the driver in standby mode.
+    fn standby(mut self) -> Result<Standby<Self>, DEV::Error, Self::PinError>
+    where
+        Self: Sized,
+    {
+        command(self.spi_device(), Command::STANDBY)?;
+        Ok(Standby { ads: self })
+    }
+
     /// Read a register of the ADS1292
     #[inline]
-    fn read_register(&mut self, reg: Register) -> Result<u8, E> {
-        let nreg = 0x00; // n = 1, but subtract 1
-        let mut buf: [u8; 4] = [Command::RREG.word() | reg.addr(), nreg, 0x00, 0x00];
-        self.spi_device().transfer(&mut buf)?;
-        Ok(buf[2])
+    fn read_register(&mut self, reg: Register) -> Result<u8, DEV::Error, Self::PinError> {
+        if let Some(value) = self.register_state().cached(reg) {
+            return Ok(value);
+        }
+        let mut value = [0u8; 1];
+        self.read_registers(reg, &mut value)?;
+        Ok(value[0])
     }
 
     /// Write in register of the ADS1292
     #[inline]
-    fn write_register(&mut self, reg: Register, data: u8) -> Result<(), E> {
-        let nreg = 0x00; // n = 1, but subtract 1
-        let buf: [u8; 3] = [Command::WREG.word() | reg.addr(), nreg, data];
-        self.spi_device().write(&buf)?;
+    fn write_register(
+        &mut self,
+        reg: Register,
+        data: u8,
+    ) -> Result<(), DEV::Error, Self::PinError> {
+        self.write_registers(reg, &[data])
+    }
+
+    /// Read consecutive registers starting at `start` in a single transaction, filling `data`.
+    ///
+    /// This always reads from the bus, and refreshes the shadow cache if it is enabled.
+    fn read_registers(
+        &mut self,
+        start: Register,
+        data: &mut [u8],
+    ) -> Result<(), DEV::Error, Self::PinError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut frame = RegisterFrame::read(start, data.len())?;
+        self.spi_device()
+            .transfer(frame.bytes_mut())
+            .map_err(Ads129xxError::convert_pin)?;
+        data.copy_from_slice(frame.data());
+        self.register_state_mut().record(start, data);
         Ok(())
     }
 
-    simple_register!(read_conf1, write_conf1, CONFIG1, Conf1);
-    simple_register!(read_conf2, write_conf2, CONFIG2, Conf2);
-    simple_register!(read_loff, write_loff, LOFF, Loff);
-    simple_register!(read_loff_sens, write_loff_sens, LOFF_SENS, LoffSense);
-    simple_register!(read_chan1, write_chan1, CH1SET, ChannelSettings);
-    simple_register!(read_chan2, write_chan2, CH2SET, ChannelSettings);
-    simple_register!(read_rld_sens, write_rld_sens, RLD_SENS, RLDSenseSelection);
-    simple_register!(read_resp_conf2, write_resp_conf2, RESP2, RespConf2);
+    /// Write consecutive registers starting at `start` in a single transaction.
+    ///
+    /// Fails with `Ads129xxError::ReadOnlyRegister` if the range includes the ID register.
+    fn write_registers(
+        &mut self,
+        start: Register,
+        data: &[u8],
+    ) -> Result<(), DEV::Error, Self::PinError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let frame = RegisterFrame::write(start, data)?;
+        self.spi_device()
+            .write(frame.bytes())
+            .map_err(Ads129xxError::convert_pin)?;
+        if self.verify_writes() {
+            let mut read = [0u8; REGISTER_COUNT];
+            let read = &mut read[..data.len()];
+            self.read_registers(start, read)?;
+            check_readback(start, data, read)?;
+        }
+        self.register_state_mut().record(start, data);
+        self.registers_written(start, data);
+        Ok(())
+    }
+
+    /// Read the whole register map in a single transaction
+    fn dump_registers(&mut self) -> Result<RegisterMap, DEV::Error, Self::PinError> {
+        let mut map = RegisterMap::default();
+        self.read_registers(Register::ID, &mut map.0)?;
+        Ok(map)
+    }
+
+    /// Read every writable register into a configuration snapshot
+    fn read_config(&mut self) -> Result<Ads1292Config, DEV::Error, Self::PinError> {
+        Ok(Ads1292Config::from_registers(&self.dump_registers()?))
+    }
+
+    /// Apply a configuration, only writing the fields that differ from the current one.
+    ///
+    /// Returns the fields that were written.
+    fn apply_config(
+        &mut self,
+        config: &Ads1292Config,
+    ) -> Result<ConfigDiff, DEV::Error, Self::PinError> {
+        let diff = self.read_config()?.diff(config);
+        for field in diff.iter() {
+            self.write_register(field.register(), config.field(field))?;
+        }
+        Ok(diff)
+    }
+
+    /// Read and decode the ID register
+    #[inline]
+    fn read_device_id(&mut self) -> Result<DeviceId, DEV::Error, Self::PinError> {
+        Ok(DeviceId(self.read_register(Register::ID)?))
+    }
+
+    /// Read the lead-off status register.
+    ///
+    /// Unlike the status word in a data block, this includes the CLK_DIV bit.
+    #[inline]
+    fn read_lead_off_status(&mut self) -> Result<LeadOffStatus, DEV::Error, Self::PinError> {
+        Ok(LeadOffStatus {
+            status: self.read_register(Register::LOFF_STAT)?,
+        })
+    }
+
+    /// Read the gain of a channel and the reference selection, to convert its data to volts.
+    ///
+    /// Returns `None` if the registers do not determine the scaling, see
+    /// `Scaling::from_registers`.
+    fn read_scaling(&mut self, ch: Channel) -> Result<Option<Scaling>, DEV::Error, Self::PinError> {
+        let chan = match ch {
+            Channel::Ch1 => self.read_chan1()?,
+            Channel::Ch2 => self.read_chan2()?,
+        };
+        Ok(Scaling::from_registers(chan, self.read_conf2()?))
+    }
+
+    simple_register!(read_conf1, write_conf1, modify_conf1, CONFIG1, Conf1);
+    simple_register!(read_conf2, write_conf2, modify_conf2, CONFIG2, Conf2);
+    simple_register!(read_loff, write_loff, modify_loff, LOFF, Loff);
+    simple_register!(
+        read_loff_sens,
+        write_loff_sens,
+        modify_loff_sens,
+        LOFF_SENS,
+        LoffSense
+    );
+    simple_register!(
+        read_chan1,
+        write_chan1,
+        modify_chan1,
+        CH1SET,
+        ChannelSettings
+    );
+    simple_register!(
+        read_chan2,
+        write_chan2,
+        modify_chan2,
+        CH2SET,
+        ChannelSettings
+    );
+    simple_register!(
+        read_rld_sens,
+        write_rld_sens,
+        modify_rld_sens,
+        RLD_SENS,
+        RLDSenseSelection
+    );
+    simple_register!(
+        read_resp_conf1,
+        write_resp_conf1,
+        modify_resp_conf1,
+        RESP1,
+        RespConf1
+    );
+    simple_register!(
+        read_resp_conf2,
+        write_resp_conf2,
+        modify_resp_conf2,
+        RESP2,
+        RespConf2
+    );
+    simple_register!(read_gpio, write_gpio, modify_gpio, GPIO, GpioConfig);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ads1292r::Ads1292R;
+    use crate::fake::{fake_ads1292, FakeAds, FakeState};
+
+    #[test]
+    fn lead_off_status_register() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state);
+        state.regs.borrow_mut()[Register::LOFF_STAT] = 0x49;
+        let status = ads.read_lead_off_status().unwrap();
+        assert_eq!(status.clk_div(), 1);
+        assert!(status.in2n_off() && status.in1p_off());
+        assert!(!status.rld_stat() && !status.in2p_off() && !status.in1n_off());
+
+        // The status is live, so it is read from the bus even with the cache enabled
+        ads.enable_register_cache().unwrap();
+        state.regs.borrow_mut()[Register::LOFF_STAT] = 0x00;
+        state.transactions();
+        assert_eq!(ads.read_lead_off_status().unwrap().status, 0x00);
+        assert_eq!(state.transactions(), 1);
+    }
+
+    #[test]
+    fn burst_access() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state);
+        ads.write_registers(Register::CH1SET, &[0x60, 0x81])
+            .unwrap();
+        assert_eq!(state.transactions(), 1);
+        assert_eq!(state.regs.borrow()[Register::CH2SET], 0x81);
+
+        let map = ads.dump_registers().unwrap();
+        assert_eq!(state.transactions(), 1);
+        assert_eq!(map, *state.regs.borrow());
+
+        match ads.write_registers(Register::GPIO, &[0x0C, 0x00]) {
+            Err(Ads129xxError::InvalidRegisterRange) => (),
+            _ => panic!("expected InvalidRegisterRange"),
+        }
+    }
+
+    #[test]
+    fn register_cache_answers_reads() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state);
+        ads.enable_register_cache().unwrap();
+        assert_eq!(state.transactions(), 1);
+
+        ads.modify_chan1(|chan| chan.set_gain(GainSetting::G12))
+            .unwrap();
+        assert_eq!(state.transactions(), 1);
+        assert_eq!(ads.read_chan1().unwrap().0, 0x60);
+        assert_eq!(state.transactions(), 0);
+
+        // Unchanged values are not written
+        ads.modify_conf2(|conf| conf.set_int_test(false)).unwrap();
+        assert_eq!(state.transactions(), 0);
+
+        // Live registers always go to the bus
+        ads.read_gpio().unwrap();
+        assert_eq!(state.transactions(), 1);
+
+        // The ID cannot be written, so the cache cannot hold a fake one
+        match ads.write_register(Register::ID, 0x00) {
+            Err(Ads129xxError::ReadOnlyRegister(Register::ID)) => (),
+            _ => panic!("expected ReadOnlyRegister"),
+        }
+        assert_eq!(ads.read_device_id().unwrap().0, 0x53);
+        assert_eq!(state.transactions(), 1);
+
+        // A reset reloads the cache with the default values
+        state.regs.borrow_mut()[Register::CH1SET] = 0x00;
+        ads.hardware_reset().unwrap();
+        assert_eq!(ads.read_chan1().unwrap().0, 0x00);
+    }
+
+    #[test]
+    fn verify_detects_ignored_writes() {
+        let state = FakeState::new();
+        let mut ads = fake_ads1292(&state);
+        ads.set_verify_writes(true);
+
+        ads.write_register(Register::CONFIG1, 0x03).unwrap();
+
+        state.ignore_writes.set(true);
+        match ads.write_register(Register::CONFIG1, 0x04) {
+            Err(Ads129xxError::RegisterMismatch { reg, wrote, read }) => {
+                assert_eq!(reg, Register::CONFIG1);
+                assert_eq!(wrote, 0x04);
+                assert_eq!(read, 0x03);
+            }
+            _ => panic!("expected RegisterMismatch"),
+        }
+    }
+
+    #[test]
+    fn reset_returns_to_defaults() {
+        let state = FakeState::new();
+        state.regs.borrow_mut()[Register::ID] = 0x73;
+        let mut ads = Ads1292R::new(FakeAds(&state)).unwrap();
+        ads.enable_register_cache().unwrap();
+        ads.write_register(Register::RESP1, 0xC2).unwrap();
+        assert!(ads.respiration_enabled());
+        match ads.cmd(Command::RESET) {
+            Err(Ads129xxError::RestrictedCommand(Command::RESET)) => (),
+            _ => panic!("expected RestrictedCommand"),
+        }
+
+        state.transactions();
+        ads.reset().unwrap();
+        // RESET, SDATAC and reloading the cache
+        assert_eq!(state.transactions(), 3);
+        assert!(!ads.respiration_enabled());
+        assert_eq!(ads.cached_registers().unwrap()[Register::RESP1], 0x00);
+    }
 }
@@ -0,0 +1,85 @@
+//! The ADS129xx has a number of operating modes, and only accepts some commands in each of them.
+//! The drivers model these as types, and switching modes consumes the old one:
+//!
+//! - Command mode (SDATAC), entered by `new`: the driver types themselves, like
+//!   `ads1292::Ads1292`. Only these give access to the registers, and RDATA reads.
+//! - Continuous read mode (RDATAC): the data streams, like
+//!   `ads1292::data_stream::Ads1292DataStream`, entered with `into_data_stream` and left with
+//!   `into_inner`.
+//! - Standby: `Standby`, entered with `Ads129xx::standby` and left with `Standby::wakeup`.
+//! - Powered down: `PowerDown`, entered with `Ads1292::power_down` and left with
+//!   `PowerDown::power_up`. This needs the driver to own the PWDN/RESET pin.
+//!
+//! `Ads129xx::cmd` refuses the commands that switch modes, so the types stay in line with the
+//! device.
+//!
+//! ```norun
+//!let standby = ads1292.standby()?;
+//!// ... save power ...
+//!let mut ads1292 = standby.wakeup()?;
+//!ads1292.read_conf1()?;
+//!```
+
+use crate::spi::Interface;
+use crate::{command, Ads129xx, Command, Result};
+
+/// Device clock periods to wait after WAKEUP before sending the next command
+const WAKEUP_TCLK: u16 = 4;
+
+/// A device in standby mode, entered with the STANDBY command.
+///
+/// The reference and oscillator keep running, and the registers keep their values.
+pub struct Standby<A> {
+    pub(crate) ads: A,
+}
+
+impl<A> Standby<A> {
+    /// Send the WAKEUP command, and return the driver in command mode.
+    pub fn wakeup<DEV>(mut self) -> Result<A, DEV::Error, A::PinError>
+    where
+        A: Ads129xx<DEV>,
+        DEV: Interface,
+    {
+        command(self.ads.spi_device(), Command::WAKEUP)?;
+        self.ads.spi_device().wait_tclk(WAKEUP_TCLK);
+        Ok(self.ads)
+    }
+}
+
+/// A powered down device, entered by holding the PWDN/RESET pin low.
+pub struct PowerDown<A> {
+    pub(crate) ads: A,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{fake_ads1292, FakePin, FakeState};
+    use core::cell::Cell;
+
+    #[test]
+    fn mode_transitions() {
+        let state = FakeState::new();
+        let ads = fake_ads1292(&state);
+        let ads = ads.standby().unwrap().wakeup().unwrap();
+        assert_eq!(state.transactions(), 2);
+
+        let (high, pulses) = (Cell::new(false), Cell::new(0));
+        let ads = ads
+            .with_reset_pin(FakePin {
+                high: &high,
+                pulses: &pulses,
+            })
+            .unwrap();
+        let powered_down = ads.power_down().unwrap();
+        assert!(!high.get());
+        assert_eq!(state.transactions(), 0);
+
+        let mut ads = powered_down.power_up().unwrap();
+        assert!(high.get());
+        // A reset pulse, and SDATAC after it
+        assert_eq!(pulses.get(), 2);
+        assert_eq!(state.transactions(), 1);
+        ads.read_conf1().unwrap();
+    }
+}
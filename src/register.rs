@@ -1,10 +1,12 @@
+use core::ops::{Index, IndexMut};
+
 use bitfield::bitfield;
 
 /// Read / write-able registers
 ///
 /// Table 14 page 39 of specification.
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Register {
     /// ID Control Register (Factory-Programmed, Read-Only)
     ID = 0x00,
@@ -32,11 +34,125 @@ pub enum Register {
     GPIO = 0x0B,
 }
 
+/// Number of registers in the register map
+pub const REGISTER_COUNT: usize = 12;
+
 impl Register {
+    /// All registers, in address order
+    pub const ALL: [Register; REGISTER_COUNT] = [
+        Register::ID,
+        Register::CONFIG1,
+        Register::CONFIG2,
+        Register::LOFF,
+        Register::CH1SET,
+        Register::CH2SET,
+        Register::RLD_SENS,
+        Register::LOFF_SENS,
+        Register::LOFF_STAT,
+        Register::RESP1,
+        Register::RESP2,
+        Register::GPIO,
+    ];
+
     #[inline]
     pub fn addr(self) -> u8 {
         self as u8
     }
+
+    /// Whether reads of this register may be answered from a shadow copy.
+    ///
+    /// LOFF_STAT and GPIO reflect live electrode and pin states, and the read-only ID register
+    /// is always read from the device.
+    pub(crate) fn cacheable(self) -> bool {
+        !matches!(self, Register::ID | Register::LOFF_STAT | Register::GPIO)
+    }
+
+    /// Whether this register can be written; the factory-programmed ID register cannot.
+    pub fn writable(self) -> bool {
+        self != Register::ID
+    }
+
+    /// The bits of a value written to this register that read back unchanged.
+    ///
+    /// The ID register is read-only, and of LOFF_STAT only CLK_DIV can be written. The GPIO
+    /// data bits read back the pin level, which only matches the written value for outputs.
+    pub(crate) fn readback_mask(self, value: u8) -> u8 {
+        match self {
+            Register::ID => 0x00,
+            Register::LOFF_STAT => 0x40,
+            Register::GPIO => {
+                let outputs = !value >> 2 & 0x03;
+                0x0C | outputs
+            }
+            _ => 0xFF,
+        }
+    }
+}
+
+/// Contents of the whole register map, indexed by `Register`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterMap(pub [u8; REGISTER_COUNT]);
+
+impl Index<Register> for RegisterMap {
+    type Output = u8;
+
+    fn index(&self, reg: Register) -> &u8 {
+        &self.0[reg.addr() as usize]
+    }
+}
+
+impl IndexMut<Register> for RegisterMap {
+    fn index_mut(&mut self, reg: Register) -> &mut u8 {
+        &mut self.0[reg.addr() as usize]
+    }
+}
+
+/// Device models that can be told apart by their ID register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    /// 16-bit, 1 channel
+    Ads1191,
+    /// 16-bit, 2 channels
+    Ads1192,
+    /// 24-bit, 1 channel
+    Ads1291,
+    /// 24-bit, 2 channels
+    Ads1292,
+    /// 24-bit, 2 channels with respiration front-end
+    Ads1292R,
+}
+
+bitfield! {
+    /// Contents of the factory-programmed ID register.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct DeviceId(u8);
+    impl Debug;
+
+    /// Revision identification: 0b010 for the ADS1x9x family, 0b011 for the ADS1292R.
+    pub u8, rev_id, _: 7, 5;
+    /// Always reads high.
+    pub fixed_high, _: 4;
+    /// Always reads low.
+    pub u8, fixed_low, _: 3, 2;
+    /// Channel count and resolution identification.
+    pub u8, dev_id, _: 1, 0;
+}
+
+impl DeviceId {
+    /// Decode the device model, if the ID is a valid one.
+    pub fn model(&self) -> Option<Model> {
+        if !self.fixed_high() || self.fixed_low() != 0 {
+            return None;
+        }
+        match (self.rev_id(), self.dev_id()) {
+            (0b010, 0b00) => Some(Model::Ads1191),
+            (0b010, 0b01) => Some(Model::Ads1192),
+            (0b010, 0b10) => Some(Model::Ads1291),
+            (0b010, 0b11) => Some(Model::Ads1292),
+            (0b011, 0b11) => Some(Model::Ads1292R),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -52,6 +168,23 @@ pub enum SampleRate {
     Unknown = 0b111,
 }
 
+impl SampleRate {
+    /// Samples per second with a 512 kHz modulator clock, or `None` for the reserved setting
+    pub fn sps(&self) -> Option<u16> {
+        use SampleRate::*;
+        Some(match self {
+            Sps125 => 125,
+            Sps250 => 250,
+            Sps500 => 500,
+            KSps1 => 1000,
+            KSps2 => 2000,
+            KSps4 => 4000,
+            KSps8 => 8000,
+            Unknown => return None,
+        })
+    }
+}
+
 impl From<u8> for SampleRate {
     fn from(x: u8) -> Self {
         use SampleRate::*;
@@ -76,7 +209,9 @@ impl From<SampleRate> for u8 {
 
 bitfield! {
     /// Configuration for the register that configures each ADC channel sample rate.
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct Conf1(u8);
+    impl Debug;
 
     /// The single shot conversion mode, otherwise use a continuous conversion mode.
     pub single_shot, set_single_shot: 7;
@@ -86,7 +221,9 @@ bitfield! {
 
 bitfield! {
     /// Configuration for the register that configures the test signal, clock, reference and LOFF buffer.
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct Conf2(u8);
+    impl Debug;
 
     /// Power down the lead-off comparators.
     pub pdb_loff_comp, set_pdb_loff_comp: 6;
@@ -133,7 +270,9 @@ impl From<LeadOffCurrentMagnitude> for u8 {
 
 bitfield! {
     /// Configuration for the register that configures the lead-off detection operation.
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct Loff(u8);
+    impl Debug;
 
     /// Power down the lead-off comparators.
     pub comp_th, set_comp_th: 7, 5;
@@ -145,7 +284,9 @@ bitfield! {
 
 bitfield! {
     /// Configuration for the register that selects the positive and negative side from each channel for lead-off detection.
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct LoffSense(u8);
+    impl Debug;
 
     /// Controls the direction of the current used for lead-off derivation for channel 2
     pub flip2, set_flip2: 5;
@@ -174,6 +315,23 @@ pub enum GainSetting {
     Unknown = 0b111,
 }
 
+impl GainSetting {
+    /// The amplification factor, or `None` for the reserved setting
+    pub fn factor(&self) -> Option<u8> {
+        use GainSetting::*;
+        Some(match self {
+            G1 => 1,
+            G2 => 2,
+            G3 => 3,
+            G4 => 4,
+            G6 => 6,
+            G8 => 8,
+            G12 => 12,
+            Unknown => return None,
+        })
+    }
+}
+
 impl From<u8> for GainSetting {
     fn from(x: u8) -> Self {
         use GainSetting::*;
@@ -248,9 +406,18 @@ impl From<InputSelection> for u8 {
     }
 }
 
+/// An input channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Ch1,
+    Ch2,
+}
+
 bitfield! {
     /// Configuration for the register that configures the power mode, PGA gain, and multiplexer settings channels.
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct ChannelSettings(u8);
+    impl Debug;
 
     /// Power down the channel.
     pub pd, set_pd: 7;
@@ -289,7 +456,9 @@ impl From<ChopFrequency> for u8 {
 
 bitfield! {
     /// Configuration for the register that controls the selection of the positive and negative signals from each channel for right leg drive derivation.
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct RLDSenseSelection(u8);
+    impl Debug;
 
     /// Determines the PGA chop frequency.
     pub u8, from into ChopFrequency, chop, set_chop: 7, 6;
@@ -308,13 +477,34 @@ bitfield! {
     pub rld1p, set_rld1p: 0;
 }
 
+bitfield! {
+    /// Configuration for the register that controls the respiration functionality (ADS1292R only).
+    ///
+    /// **Warning**: bit 1 must always be written with '1'.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct RespConf1(u8);
+    impl Debug;
+
+    /// Enables the respiration demodulation circuitry on channel 1.
+    pub resp_demod_en1, set_resp_demod_en1: 7;
+    /// Enables the respiration modulation circuitry on channel 1.
+    pub resp_mod_en, set_resp_mod_en: 6;
+    /// Controls the phase of the respiration demodulation control signal,
+    /// in steps of 11.25° at 32 kHz or 22.5° at 64 kHz.
+    pub u8, resp_ph, set_resp_ph: 5, 2;
+    /// Use an external respiration clock (true : 1) instead of the internal one (false : 0).
+    pub resp_ctrl, set_resp_ctrl: 0;
+}
+
 bitfield! {
     /// Configuration for the register that controls the respiration and calibration functionality.
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct RespConf2(u8);
+    impl Debug;
 
     /// Enables offset calibration
     pub calib_on, set_calib_on: 7;
-    /// Controls the respiration control frequency when RESP_CTRL = 0.
+    /// Controls the respiration control frequency when RESP_CTRL = 0: 64 kHz (true : 1) or 32 kHz (false : 0).
     ///
     /// **Warning**: this bit must be written with '1' for the ADS1291 and ADS1292.
     pub resp_freq_64khz, set_resp_freq_64khz: 2;
@@ -322,3 +512,47 @@ bitfield! {
     /// Can be fed externally (false : 0) or internally by using (AVDD – AVSS) / 2 (true : 1).
     pub rldref_int, set_rldref_int: 1;
 }
+
+bitfield! {
+    /// Configuration for the register that controls the general-purpose I/O pins.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct GpioConfig(u8);
+    impl Debug;
+
+    /// Configures GPIO 2 as input (true : 1) or output (false : 0).
+    pub gpio_c_2, set_gpio_c_2: 3;
+    /// Configures GPIO 1 as input (true : 1) or output (false : 0).
+    pub gpio_c_1, set_gpio_c_1: 2;
+    /// Level of GPIO 2; read when it is an input, driven when it is an output.
+    pub gpio_d_2, set_gpio_d_2: 1;
+    /// Level of GPIO 1; read when it is an input, driven when it is an output.
+    pub gpio_d_1, set_gpio_d_1: 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_id_model() {
+        assert_eq!(DeviceId(0x50).model(), Some(Model::Ads1191));
+        assert_eq!(DeviceId(0x51).model(), Some(Model::Ads1192));
+        assert_eq!(DeviceId(0x52).model(), Some(Model::Ads1291));
+        assert_eq!(DeviceId(0x53).model(), Some(Model::Ads1292));
+        assert_eq!(DeviceId(0x73).model(), Some(Model::Ads1292R));
+        assert_eq!(DeviceId(0x72).model(), None);
+        assert_eq!(DeviceId(0x43).model(), None);
+        assert_eq!(DeviceId(0x57).model(), None);
+        assert_eq!(DeviceId(0xFF).model(), None);
+    }
+
+    #[test]
+    fn gpio_readback_mask() {
+        // Both inputs: only the control bits are compared
+        assert_eq!(Register::GPIO.readback_mask(0x0C), 0x0C);
+        // GPIO1 output, GPIO2 input
+        assert_eq!(Register::GPIO.readback_mask(0x09), 0x0D);
+        // Both outputs
+        assert_eq!(Register::GPIO.readback_mask(0x03), 0x0F);
+    }
+}
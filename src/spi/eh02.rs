@@ -0,0 +1,410 @@
+use core::convert::TryFrom;
+
+use embedded_hal_02::blocking::spi as bspi;
+use embedded_hal_02::digital::v2::OutputPin;
+use embedded_hal_02::spi as eh_spi;
+use embedded_hal_02::timer::CountDown;
+
+use crate::spi::paced::gap_elapsed;
+use crate::spi::{Clock, Interface, TimerTicks, Timing};
+use crate::{Ads129xxError, Result};
+
+/// SPI mode, as an embedded-hal 0.2 type
+pub const MODE: eh_spi::Mode = eh_spi::MODE_1;
+
+/// A SPI device also triggering the nCS-pin when suited.
+///
+/// The nCS pin may be fallible, like pins behind a GPIO expander; its errors are reported as
+/// `Ads129xxError::Pin`. Every transaction releases nCS again, also when it fails.
+///
+/// Built on the embedded-hal 0.2 blocking SPI traits, a nCS `OutputPin` and a `CountDown` timer
+/// used for the delays around each transaction. `new` expects the timer to overflow at 500 kHz;
+/// use `with_timing` for other timer rates.
+///
+/// Without a clock, every transfer waits the full delays. With a `Clock` set by `with_clock`,
+/// `Interface::fast_transfer` skips them when the previous transaction ended long enough ago,
+/// and returns `WouldBlock` otherwise, like `PacedInterface` does.
+pub struct SpiDevice<SPI, NCS, TIM, C = NoClock> {
+    /// Underlying peripheral
+    spi: SPI,
+    /// nCS
+    ncs: NCS,
+    /// Timer for nCS delay
+    timer: TIM,
+    /// Overflow rate of the timer
+    timer_hz: u32,
+    /// Delays around transactions
+    timing: Timing,
+    /// Delays around transactions, in timer overflows
+    ticks: TimerTicks,
+    /// Time source for the fast path
+    clock: C,
+    /// End of the last fast transaction, while it is less than a gap ago
+    last: Option<u32>,
+}
+
+/// Stands in for the clock of a `SpiDevice` without a fast path.
+pub struct NoClock;
+
+impl<SPI, NCS, TIM, E> SpiDevice<SPI, NCS, TIM>
+where
+    SPI: bspi::Write<u8, Error = E> + bspi::Transfer<u8, Error = E>,
+    NCS: OutputPin,
+    TIM: CountDown,
+{
+    /// Create a new SPI device
+    pub fn new(spi: SPI, ncs: NCS, timer: TIM) -> core::result::Result<Self, NCS::Error> {
+        Self::with_timing(spi, ncs, timer, 500_000, Timing::CONSERVATIVE)
+    }
+
+    /// Create a new SPI device with a timer overflowing at `timer_hz`, and the given delays, for
+    /// instance `Timing::from_fclk(fclk_hz)`.
+    pub fn with_timing(
+        spi: SPI,
+        mut ncs: NCS,
+        timer: TIM,
+        timer_hz: u32,
+        timing: Timing,
+    ) -> core::result::Result<Self, NCS::Error> {
+        ncs.set_high()?;
+
+        Ok(SpiDevice {
+            spi,
+            ncs,
+            timer,
+            timer_hz,
+            timing,
+            ticks: timing.ticks(timer_hz),
+            clock: NoClock,
+            last: None,
+        })
+    }
+
+    /// Check the spacing of fast transfers against `clock`, a free-running microsecond counter.
+    pub fn with_clock<C: Clock>(self, clock: C) -> SpiDevice<SPI, NCS, TIM, C> {
+        SpiDevice {
+            spi: self.spi,
+            ncs: self.ncs,
+            timer: self.timer,
+            timer_hz: self.timer_hz,
+            timing: self.timing,
+            ticks: self.ticks,
+            clock,
+            last: None,
+        }
+    }
+}
+
+impl<SPI, NCS, TIM, E, C> SpiDevice<SPI, NCS, TIM, C>
+where
+    SPI: bspi::Write<u8, Error = E> + bspi::Transfer<u8, Error = E>,
+    NCS: OutputPin,
+    TIM: CountDown,
+{
+    /// Run `f` with nCS pulled low. A fast transaction skips the setup delay after pulling nCS
+    /// low and the idle time after releasing it; the hold delay in between is always waited.
+    fn select<F>(&mut self, fast: bool, f: F) -> Result<(), E, NCS::Error>
+    where
+        F: FnOnce(&mut SPI) -> core::result::Result<(), E>,
+    {
+        if let Err(e) = self.ncs.set_low() {
+            // Whatever state the pin ended up in, try to leave the device deselected.
+            let _ = self.ncs.set_high();
+            return Err(Ads129xxError::Pin(e));
+        }
+        if !fast {
+            crate::util::wait(&mut self.timer, self.ticks.cs_setup.into());
+        }
+        let res = f(&mut self.spi);
+        crate::util::wait(&mut self.timer, self.ticks.cs_hold.into());
+        let deselect = self.ncs.set_high();
+        if !fast {
+            crate::util::wait(&mut self.timer, self.ticks.idle.into());
+        }
+        res?; // Drop out of function with SPIError only after setting NCS.
+        deselect.map_err(Ads129xxError::Pin)
+    }
+
+    /// Transfer the buffer to the device, the passed buffer will contain the read data.
+    #[inline]
+    pub fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E, NCS::Error> {
+        self.select(false, |spi| spi.transfer(buffer).map(|_| ()))
+    }
+
+    /// Write a number of bytes to the device.
+    #[inline]
+    pub fn write(&mut self, buffer: &[u8]) -> Result<(), E, NCS::Error> {
+        self.select(false, |spi| spi.write(buffer))
+    }
+
+    /// Blockingly wait `i` periods of 2 microseconds, rounded up to whole timer overflows.
+    pub fn wait(&mut self, i: u16) {
+        let ticks = (u64::from(i) * 2 * u64::from(self.timer_hz)).div_ceil(1_000_000);
+        crate::util::wait(&mut self.timer, u32::try_from(ticks).unwrap_or(u32::MAX));
+    }
+
+    /// Consume self and release inner resources.
+    pub fn into_inner(self) -> (SPI, NCS, TIM, C) {
+        (self.spi, self.ncs, self.timer, self.clock)
+    }
+}
+
+impl<SPI, NCS, TIM, E, C> SpiDevice<SPI, NCS, TIM, C>
+where
+    SPI: bspi::Write<u8, Error = E> + bspi::Transfer<u8, Error = E>,
+    NCS: OutputPin,
+    TIM: CountDown,
+    C: Clock,
+{
+    /// Wait until a delayed transaction can start after a fast one
+    fn pace(&mut self) {
+        while !gap_elapsed(&mut self.clock, &mut self.last, self.timing) {}
+    }
+}
+
+impl<SPI, NCS, TIM, E> Interface for SpiDevice<SPI, NCS, TIM>
+where
+    SPI: bspi::Write<u8, Error = E> + bspi::Transfer<u8, Error = E>,
+    NCS: OutputPin,
+    TIM: CountDown,
+{
+    type Error = E;
+    type PinError = NCS::Error;
+
+    #[inline]
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E, NCS::Error> {
+        SpiDevice::transfer(self, buffer)
+    }
+
+    #[inline]
+    fn write(&mut self, buffer: &[u8]) -> Result<(), E, NCS::Error> {
+        SpiDevice::write(self, buffer)
+    }
+
+    #[inline]
+    fn wait(&mut self, i: u16) {
+        SpiDevice::wait(self, i)
+    }
+
+    #[inline]
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+}
+
+impl<SPI, NCS, TIM, E, C> Interface for SpiDevice<SPI, NCS, TIM, C>
+where
+    SPI: bspi::Write<u8, Error = E> + bspi::Transfer<u8, Error = E>,
+    NCS: OutputPin,
+    TIM: CountDown,
+    C: Clock,
+{
+    type Error = E;
+    type PinError = NCS::Error;
+
+    #[inline]
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E, NCS::Error> {
+        self.pace();
+        SpiDevice::transfer(self, buffer)
+    }
+
+    #[inline]
+    fn fast_transfer(&mut self, buffer: &mut [u8]) -> nb::Result<(), Ads129xxError<E, NCS::Error>> {
+        self.poll_ready()?;
+        let res = self.select(true, |spi| spi.transfer(buffer).map(|_| ()));
+        self.last = Some(self.clock.now_us());
+        Ok(res?)
+    }
+
+    #[inline]
+    fn write(&mut self, buffer: &[u8]) -> Result<(), E, NCS::Error> {
+        self.pace();
+        SpiDevice::write(self, buffer)
+    }
+
+    #[inline]
+    fn poll_ready(&mut self) -> nb::Result<(), Ads129xxError<E, NCS::Error>> {
+        if gap_elapsed(&mut self.clock, &mut self.last, self.timing) {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    #[inline]
+    fn wait(&mut self, i: u16) {
+        SpiDevice::wait(self, i)
+    }
+
+    #[inline]
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    struct FakeSpi(bool);
+
+    impl bspi::Transfer<u8> for FakeSpi {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> core::result::Result<&'w [u8], ()> {
+            if self.0 {
+                Err(())
+            } else {
+                Ok(words)
+            }
+        }
+    }
+
+    impl bspi::Write<u8> for FakeSpi {
+        type Error = ();
+
+        fn write(&mut self, _words: &[u8]) -> core::result::Result<(), ()> {
+            if self.0 {
+                Err(())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// A nCS pin that fails to go high while `fail_high` is set
+    struct FakeNcs<'a> {
+        high: &'a Cell<bool>,
+        fail_high: &'a Cell<bool>,
+    }
+
+    impl OutputPin for FakeNcs<'_> {
+        type Error = &'static str;
+
+        fn set_low(&mut self) -> core::result::Result<(), &'static str> {
+            self.high.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> core::result::Result<(), &'static str> {
+            if self.fail_high.get() {
+                return Err("expander NAK");
+            }
+            self.high.set(true);
+            Ok(())
+        }
+    }
+
+    struct FakeTimer;
+
+    impl CountDown for FakeTimer {
+        type Time = ();
+
+        fn start<T: Into<()>>(&mut self, _count: T) {}
+
+        fn wait(&mut self) -> nb::Result<(), void::Void> {
+            Ok(())
+        }
+    }
+
+    /// A timer that counts its overflows
+    struct CountingTimer<'a>(&'a Cell<u32>);
+
+    impl CountDown for CountingTimer<'_> {
+        type Time = ();
+
+        fn start<T: Into<()>>(&mut self, _count: T) {}
+
+        fn wait(&mut self) -> nb::Result<(), void::Void> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn wait_converts_periods_to_ticks() {
+        let (high, fail_high) = (Cell::new(false), Cell::new(false));
+        let ncs = FakeNcs {
+            high: &high,
+            fail_high: &fail_high,
+        };
+        let ticks = Cell::new(0);
+        let timer = CountingTimer(&ticks);
+        let mut spi =
+            SpiDevice::with_timing(FakeSpi(false), ncs, timer, 32_768, Timing::CONSERVATIVE)
+                .unwrap();
+
+        // 18 tCLK of 2 us are 36 us, or 1.2 overflows of a 32.768 kHz timer
+        spi.wait_tclk(18);
+        assert_eq!(ticks.get(), 2);
+        ticks.set(0);
+        spi.wait(500);
+        assert_eq!(ticks.get(), 33);
+    }
+
+    #[test]
+    fn chip_select_errors() {
+        let (high, fail_high) = (Cell::new(false), Cell::new(false));
+        let ncs = FakeNcs {
+            high: &high,
+            fail_high: &fail_high,
+        };
+        let mut spi = SpiDevice::new(FakeSpi(true), ncs, FakeTimer).unwrap();
+        assert!(high.get());
+
+        // A failing transfer still releases nCS
+        match spi.write(&[0x08]) {
+            Err(Ads129xxError::SpiError(())) => (),
+            _ => panic!("expected SpiError"),
+        }
+        assert!(high.get());
+
+        let (_, ncs, timer, _) = spi.into_inner();
+        let mut spi = SpiDevice::new(FakeSpi(false), ncs, timer).unwrap();
+        fail_high.set(true);
+        match spi.write(&[0x08]) {
+            Err(Ads129xxError::Pin("expander NAK")) => (),
+            _ => panic!("expected Pin"),
+        }
+    }
+
+    struct FakeClock<'a>(&'a Cell<u32>);
+
+    impl Clock for FakeClock<'_> {
+        fn now_us(&mut self) -> u32 {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn fast_transfer_checks_spacing() {
+        let (high, fail_high) = (Cell::new(false), Cell::new(false));
+        let ncs = FakeNcs {
+            high: &high,
+            fail_high: &fail_high,
+        };
+        let (ticks, now) = (Cell::new(0), Cell::new(0));
+        let mut spi = SpiDevice::with_timing(
+            FakeSpi(false),
+            ncs,
+            CountingTimer(&ticks),
+            1_000_000,
+            Timing::INTERNAL_CLOCK,
+        )
+        .unwrap()
+        .with_clock(FakeClock(&now));
+        let mut buf = [0u8; 9];
+
+        // Only the hold delay is waited
+        spi.fast_transfer(&mut buf).unwrap();
+        assert_eq!(ticks.get(), u32::from(spi.ticks.cs_hold));
+        assert!(high.get());
+        match spi.fast_transfer(&mut buf) {
+            Err(nb::Error::WouldBlock) => (),
+            _ => panic!("expected WouldBlock"),
+        }
+        now.set(Timing::INTERNAL_CLOCK.idle_ns.div_ceil(1000) + 1);
+        spi.fast_transfer(&mut buf).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use core::convert::{Infallible, TryFrom};
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::{self as hal_spi, Operation};
+
+use crate::{Ads129xxError, Result};
+
+/// embedded-hal 0.2 SPI interface
+#[cfg(feature = "eh02")]
+pub mod eh02;
+
+#[cfg(feature = "eh02")]
+pub use eh02::SpiDevice;
+
+/// Clock-paced SPI interface
+pub mod paced;
+/// SPI interface on a bus shared with other devices
+pub mod shared;
+/// Delays derived from the device clock
+pub mod timing;
+
+pub use paced::{Clock, PacedInterface};
+pub use shared::{SetMode, SharedBus, SharedSpiInterface};
+pub use timing::{TimerTicks, Timing};
+
+/// SPI mode
+pub const MODE: hal_spi::Mode = hal_spi::MODE_1;
+
+/// Duration of a `wait` period, in microseconds
+pub(crate) const WAIT_PERIOD_US: u32 = 2;
+
+/// The transport the drivers use to talk to the device.
+///
+/// Implemented by `SpiInterface`, `PacedInterface` and `SharedSpiInterface` for embedded-hal 1.0
+/// and, with the `eh02` feature, by the embedded-hal 0.2 `SpiDevice`.
+pub trait Interface {
+    /// Bus error
+    type Error;
+    /// Chip select error, `Infallible` if the bus takes care of chip select
+    type PinError;
+
+    /// Transfer the buffer to the device, the passed buffer will contain the read data.
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error, Self::PinError>;
+
+    /// Transfer the buffer to the device, skipping the setup delay after pulling nCS low if
+    /// enough time has passed since the previous transaction. The hold delay before releasing
+    /// nCS is kept, the device needs it to decode the last byte.
+    ///
+    /// Returns `WouldBlock` while the device is not ready for the next transaction. Interfaces
+    /// that cannot tell how much time has passed do a regular, delayed transfer instead.
+    #[inline]
+    fn fast_transfer(
+        &mut self,
+        buffer: &mut [u8],
+    ) -> nb::Result<(), Ads129xxError<Self::Error, Self::PinError>> {
+        self.poll_ready()?;
+        Ok(self.transfer(buffer)?)
+    }
+
+    /// Write a number of bytes to the device.
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error, Self::PinError>;
+
+    /// Check whether the next transaction can start without blocking.
+    ///
+    /// Interfaces that pace transactions with delays are always ready.
+    #[inline]
+    fn poll_ready(&mut self) -> nb::Result<(), Ads129xxError<Self::Error, Self::PinError>> {
+        Ok(())
+    }
+
+    /// Blockingly wait `i` periods of 2 microseconds.
+    fn wait(&mut self, i: u16);
+
+    /// The delays the interface uses, which also tell the period of the device clock.
+    ///
+    /// Defaults to `Timing::CONSERVATIVE`.
+    #[inline]
+    fn timing(&self) -> Timing {
+        Timing::CONSERVATIVE
+    }
+
+    /// Blockingly wait `n` periods of the device clock, rounded up to whole `wait` periods.
+    #[inline]
+    fn wait_tclk(&mut self, n: u16) {
+        let periods = (u32::from(n) * self.timing().tclk_ns).div_ceil(WAIT_PERIOD_US * 1000);
+        self.wait(u16::try_from(periods).unwrap_or(u16::MAX));
+    }
+}
+
+/// A SPI device on embedded-hal 1.0.
+///
+/// The HAL's `SpiDevice` takes care of chip select and bus sharing, the `DelayNs` provides the
+/// delays the ADS129xx needs around and in between transactions. Configure the bus with `MODE`.
+/// The delays are `Timing::CONSERVATIVE` unless set with `with_timing`.
+///
+/// With the `async` feature, wrapping an embedded-hal-async `SpiDevice` and `DelayNs` gives an
+/// interface for the drivers in `asynch`.
+pub struct SpiInterface<SPI, D> {
+    /// Underlying device
+    spi: SPI,
+    /// Delay for command decoding
+    delay: D,
+    /// Delays around and in between transactions
+    timing: Timing,
+}
+
+impl<SPI, D> SpiInterface<SPI, D> {
+    /// Create a new SPI interface
+    pub fn new(spi: SPI, delay: D) -> Self {
+        Self::with_timing(spi, delay, Timing::CONSERVATIVE)
+    }
+
+    /// Create a new SPI interface with the given delays, for instance
+    /// `Timing::from_fclk(fclk_hz)`
+    pub fn with_timing(spi: SPI, delay: D, timing: Timing) -> Self {
+        SpiInterface { spi, delay, timing }
+    }
+
+    /// Consume self and release inner resources.
+    pub fn into_inner(self) -> (SPI, D) {
+        (self.spi, self.delay)
+    }
+}
+
+impl<SPI, D> Interface for SpiInterface<SPI, D>
+where
+    SPI: hal_spi::SpiDevice,
+    D: DelayNs,
+{
+    type Error = SPI::Error;
+    type PinError = Infallible;
+
+    #[inline]
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), SPI::Error> {
+        let res = self.spi.transaction(&mut [
+            Operation::DelayNs(self.timing.cs_setup_ns),
+            Operation::TransferInPlace(buffer),
+            Operation::DelayNs(self.timing.cs_hold_ns),
+        ]);
+        self.delay.delay_ns(self.timing.idle_ns);
+        Ok(res?)
+    }
+
+    #[inline]
+    fn write(&mut self, buffer: &[u8]) -> Result<(), SPI::Error> {
+        let res = self.spi.transaction(&mut [
+            Operation::DelayNs(self.timing.cs_setup_ns),
+            Operation::Write(buffer),
+            Operation::DelayNs(self.timing.cs_hold_ns),
+        ]);
+        self.delay.delay_ns(self.timing.idle_ns);
+        Ok(res?)
+    }
+
+    #[inline]
+    fn wait(&mut self, i: u16) {
+        self.delay.delay_us(u32::from(i) * WAIT_PERIOD_US);
+    }
+
+    #[inline]
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+}
+
+#[cfg(feature = "async")]
+impl<SPI, D> crate::asynch::Interface for SpiInterface<SPI, D>
+where
+    SPI: embedded_hal_async::spi::SpiDevice,
+    D: embedded_hal_async::delay::DelayNs,
+{
+    type Error = SPI::Error;
+
+    #[inline]
+    async fn transfer(&mut self, buffer: &mut [u8]) -> core::result::Result<(), SPI::Error> {
+        let res = self
+            .spi
+            .transaction(&mut [
+                Operation::DelayNs(self.timing.cs_setup_ns),
+                Operation::TransferInPlace(buffer),
+                Operation::DelayNs(self.timing.cs_hold_ns),
+            ])
+            .await;
+        self.delay.delay_ns(self.timing.idle_ns).await;
+        res
+    }
+
+    #[inline]
+    async fn write(&mut self, buffer: &[u8]) -> core::result::Result<(), SPI::Error> {
+        let res = self
+            .spi
+            .transaction(&mut [
+                Operation::DelayNs(self.timing.cs_setup_ns),
+                Operation::Write(buffer),
+                Operation::DelayNs(self.timing.cs_hold_ns),
+            ])
+            .await;
+        self.delay.delay_ns(self.timing.idle_ns).await;
+        res
+    }
+
+    #[inline]
+    async fn wait(&mut self, i: u16) {
+        self.delay.delay_us(u32::from(i) * WAIT_PERIOD_US).await;
+    }
+
+    #[inline]
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+}
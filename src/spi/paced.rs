@@ -0,0 +1,240 @@
+use embedded_hal::spi::{self as hal_spi, Operation};
+
+use core::convert::Infallible;
+
+use crate::spi::{Interface, Timing, WAIT_PERIOD_US};
+use crate::{Ads129xxError, Result};
+
+/// A free-running microsecond counter, which may wrap around.
+pub trait Clock {
+    /// The current time in microseconds
+    fn now_us(&mut self) -> u32;
+}
+
+/// Whether the idle time of `timing` has passed since `last`, the end of the last transaction.
+/// Clears `last` once it has.
+pub(crate) fn gap_elapsed<C: Clock>(clock: &mut C, last: &mut Option<u32>, timing: Timing) -> bool {
+    if let Some(end) = *last {
+        let gap_us = timing.idle_ns.div_ceil(1000);
+        // Readings are truncated to whole microseconds, so the last transaction may have
+        // ended up to a microsecond later than `end`: wait one tick more.
+        if clock.now_us().wrapping_sub(end) <= gap_us {
+            return false;
+        }
+        *last = None;
+    }
+    true
+}
+
+/// A SPI device on embedded-hal 1.0 that paces transactions with a clock instead of delays.
+///
+/// The interface records when the last transaction ended, and `poll_ready` returns
+/// `WouldBlock` until the device has had the idle time of its `Timing` to process it. Instead of
+/// spinning in between commands, callers can check `Ads129xx::poll_ready` and do other work
+/// while it would block. Transactions that are started early still wait for the gap.
+///
+/// The timing defaults to `Timing::INTERNAL_CLOCK`, which also suffices for faster external
+/// clocks.
+pub struct PacedInterface<SPI, C> {
+    /// Underlying device
+    spi: SPI,
+    /// Time source
+    clock: C,
+    /// Delays around and in between transactions
+    timing: Timing,
+    /// End of the last transaction, while it is less than a gap ago
+    last: Option<u32>,
+}
+
+impl<SPI, C> PacedInterface<SPI, C> {
+    /// Create a new paced SPI interface
+    pub fn new(spi: SPI, clock: C) -> Self {
+        Self::with_timing(spi, clock, Timing::INTERNAL_CLOCK)
+    }
+
+    /// Create a new paced SPI interface with the given delays
+    pub fn with_timing(spi: SPI, clock: C, timing: Timing) -> Self {
+        PacedInterface {
+            spi,
+            clock,
+            timing,
+            last: None,
+        }
+    }
+
+    /// Consume self and release inner resources.
+    pub fn into_inner(self) -> (SPI, C) {
+        (self.spi, self.clock)
+    }
+}
+
+impl<SPI, C> PacedInterface<SPI, C>
+where
+    SPI: hal_spi::SpiDevice,
+    C: Clock,
+{
+    /// Whether the next transaction can start without waiting
+    fn gap_elapsed(&mut self) -> bool {
+        gap_elapsed(&mut self.clock, &mut self.last, self.timing)
+    }
+
+    fn paced<F>(&mut self, f: F) -> Result<(), SPI::Error>
+    where
+        F: FnOnce(&mut SPI, Timing) -> core::result::Result<(), SPI::Error>,
+    {
+        while !self.gap_elapsed() {}
+        let res = f(&mut self.spi, self.timing);
+        self.last = Some(self.clock.now_us());
+        Ok(res?)
+    }
+}
+
+impl<SPI, C> Interface for PacedInterface<SPI, C>
+where
+    SPI: hal_spi::SpiDevice,
+    C: Clock,
+{
+    type Error = SPI::Error;
+    type PinError = Infallible;
+
+    #[inline]
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), SPI::Error> {
+        self.paced(|spi, timing| {
+            spi.transaction(&mut [
+                Operation::DelayNs(timing.cs_setup_ns),
+                Operation::TransferInPlace(buffer),
+                Operation::DelayNs(timing.cs_hold_ns),
+            ])
+        })
+    }
+
+    #[inline]
+    fn fast_transfer(&mut self, buffer: &mut [u8]) -> nb::Result<(), Ads129xxError<SPI::Error>> {
+        if !self.gap_elapsed() {
+            return Err(nb::Error::WouldBlock);
+        }
+        let res = self.spi.transaction(&mut [
+            Operation::TransferInPlace(buffer),
+            Operation::DelayNs(self.timing.cs_hold_ns),
+        ]);
+        self.last = Some(self.clock.now_us());
+        Ok(res.map_err(Ads129xxError::SpiError)?)
+    }
+
+    #[inline]
+    fn write(&mut self, buffer: &[u8]) -> Result<(), SPI::Error> {
+        self.paced(|spi, timing| {
+            spi.transaction(&mut [
+                Operation::DelayNs(timing.cs_setup_ns),
+                Operation::Write(buffer),
+                Operation::DelayNs(timing.cs_hold_ns),
+            ])
+        })
+    }
+
+    #[inline]
+    fn poll_ready(&mut self) -> nb::Result<(), Ads129xxError<SPI::Error>> {
+        if self.gap_elapsed() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    #[inline]
+    fn wait(&mut self, i: u16) {
+        let start = self.clock.now_us();
+        let duration = u32::from(i) * WAIT_PERIOD_US;
+        while self.clock.now_us().wrapping_sub(start) < duration {}
+    }
+
+    #[inline]
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    struct FakeClock<'a>(&'a Cell<u32>);
+
+    impl Clock for FakeClock<'_> {
+        fn now_us(&mut self) -> u32 {
+            self.0.get()
+        }
+    }
+
+    /// Records the total delay of the last transaction
+    #[derive(Default)]
+    struct FakeSpi {
+        delay_ns: u32,
+    }
+
+    impl hal_spi::ErrorType for FakeSpi {
+        type Error = Infallible;
+    }
+
+    impl hal_spi::SpiDevice for FakeSpi {
+        fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> core::result::Result<(), Infallible> {
+            self.delay_ns = operations
+                .iter()
+                .map(|op| match op {
+                    Operation::DelayNs(ns) => *ns,
+                    _ => 0,
+                })
+                .sum();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn poll_ready_until_gap_elapsed() {
+        let now = Cell::new(u32::MAX - 10);
+        let mut spi = PacedInterface::new(FakeSpi::default(), FakeClock(&now));
+        assert!(spi.poll_ready().is_ok());
+
+        spi.write(&[0x08]).unwrap();
+        now.set(now.get().wrapping_add(7));
+        match spi.poll_ready() {
+            Err(nb::Error::WouldBlock) => (),
+            _ => panic!("expected WouldBlock"),
+        }
+
+        // 4 tCLK of the internal oscillator, which may have started a microsecond late
+        now.set(now.get().wrapping_add(1));
+        assert!(spi.poll_ready().is_err());
+        now.set(now.get().wrapping_add(1));
+        assert!(spi.poll_ready().is_ok());
+    }
+
+    #[test]
+    fn fast_transfer_checks_spacing() {
+        let now = Cell::new(0);
+        let mut spi = PacedInterface::new(FakeSpi::default(), FakeClock(&now));
+        let mut buf = [0u8; 9];
+        spi.fast_transfer(&mut buf).unwrap();
+        match spi.fast_transfer(&mut buf) {
+            Err(nb::Error::WouldBlock) => (),
+            _ => panic!("expected WouldBlock"),
+        }
+        now.set(8);
+        assert!(spi.fast_transfer(&mut buf).is_err());
+        now.set(9);
+        spi.fast_transfer(&mut buf).unwrap();
+    }
+
+    #[test]
+    fn fast_transfer_keeps_cs_hold() {
+        let now = Cell::new(0);
+        let mut spi = PacedInterface::new(FakeSpi::default(), FakeClock(&now));
+        spi.fast_transfer(&mut [0u8; 9]).unwrap();
+        let (fake, _) = spi.into_inner();
+        assert_eq!(fake.delay_ns, Timing::INTERNAL_CLOCK.cs_hold_ns);
+    }
+}
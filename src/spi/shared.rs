@@ -0,0 +1,280 @@
+use core::cell::RefCell;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::spi::{self as hal_spi, SpiBus};
+
+use crate::spi::{Interface, Timing, MODE, WAIT_PERIOD_US};
+use crate::{Ads129xxError, Result};
+
+/// A SPI bus whose mode can be changed at runtime.
+///
+/// Implement this for the bus peripheral of your HAL to share it with devices that use another
+/// SPI mode than the ADS129xx.
+pub trait SetMode: hal_spi::ErrorType {
+    /// Switch the bus to `mode`.
+    ///
+    /// This is called at the start of every transaction, so it should return early if the bus
+    /// is already in that mode.
+    fn set_mode(&mut self, mode: hal_spi::Mode) -> core::result::Result<(), Self::Error>;
+}
+
+/// A bus shared between drivers, which is locked for the duration of each transaction.
+///
+/// Implemented for `RefCell`, and with the `critical-section` feature for
+/// `critical_section::Mutex<RefCell<_>>`.
+pub trait SharedBus {
+    /// The bus peripheral
+    type Bus;
+
+    /// Run `f` with exclusive access to the bus
+    fn lock<R, F: FnOnce(&mut Self::Bus) -> R>(&self, f: F) -> R;
+}
+
+impl<BUS> SharedBus for RefCell<BUS> {
+    type Bus = BUS;
+
+    /// Borrow the bus. Panics if it is already borrowed, for instance by an interrupt handler
+    /// that preempted another driver; use a critical-section mutex in that case.
+    fn lock<R, F: FnOnce(&mut BUS) -> R>(&self, f: F) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<BUS> SharedBus for critical_section::Mutex<RefCell<BUS>> {
+    type Bus = BUS;
+
+    /// Borrow the bus inside a critical section.
+    ///
+    /// The whole transaction runs in the critical section, including the nCS setup and hold
+    /// delays, as the bus cannot be released in between. Interrupts are held off for the
+    /// transfer plus `cs_setup_ns` and `cs_hold_ns` of the `Timing`; the idle time after it is
+    /// spent outside. `SharedSpiInterface::new` keeps these delays tight with
+    /// `Timing::INTERNAL_CLOCK`; avoid `Timing::CONSERVATIVE`, which adds 80 microseconds.
+    fn lock<R, F: FnOnce(&mut BUS) -> R>(&self, f: F) -> R {
+        critical_section::with(|cs| f(&mut self.borrow_ref_mut(cs)))
+    }
+}
+
+/// A SPI interface on a bus shared with other devices.
+///
+/// Every transaction locks the bus, switches it to `MODE`, and drives the nCS pin of the
+/// ADS129xx. The nCS setup and hold delays are spent with the bus locked, so with a
+/// critical-section mutex they delay interrupts as well, see `SharedBus`. The idle time after a
+/// transaction is spent with the bus released. If all devices on the bus use the same mode, the
+/// `SpiDevice` implementations of embedded-hal-bus with a `SpiInterface` work as well.
+///
+/// The timing defaults to `Timing::INTERNAL_CLOCK`, which also suffices for faster external
+/// clocks.
+pub struct SharedSpiInterface<'a, S, CS, D> {
+    /// Shared bus
+    bus: &'a S,
+    /// nCS
+    cs: CS,
+    /// Delay for command decoding
+    delay: D,
+    /// Delays around and in between transactions
+    timing: Timing,
+}
+
+impl<'a, S, CS, D> SharedSpiInterface<'a, S, CS, D>
+where
+    CS: OutputPin,
+{
+    /// Create a new interface on a shared bus
+    pub fn new(bus: &'a S, cs: CS, delay: D) -> core::result::Result<Self, CS::Error> {
+        Self::with_timing(bus, cs, delay, Timing::INTERNAL_CLOCK)
+    }
+
+    /// Create a new interface on a shared bus with the given delays
+    pub fn with_timing(
+        bus: &'a S,
+        mut cs: CS,
+        delay: D,
+        timing: Timing,
+    ) -> core::result::Result<Self, CS::Error> {
+        cs.set_high()?;
+        Ok(SharedSpiInterface {
+            bus,
+            cs,
+            delay,
+            timing,
+        })
+    }
+
+    /// Consume self and release the nCS pin and the delay.
+    pub fn into_inner(self) -> (CS, D) {
+        (self.cs, self.delay)
+    }
+}
+
+impl<'a, S, CS, D> SharedSpiInterface<'a, S, CS, D>
+where
+    S: SharedBus,
+    S::Bus: SpiBus + SetMode,
+    CS: OutputPin,
+    D: DelayNs,
+{
+    fn transaction<F>(
+        &mut self,
+        f: F,
+    ) -> Result<(), <S::Bus as hal_spi::ErrorType>::Error, CS::Error>
+    where
+        F: FnOnce(&mut S::Bus) -> core::result::Result<(), <S::Bus as hal_spi::ErrorType>::Error>,
+    {
+        let SharedSpiInterface {
+            bus,
+            cs,
+            delay,
+            timing,
+        } = self;
+        let res = bus.lock(|bus| {
+            bus.set_mode(MODE)?;
+            if let Err(e) = cs.set_low() {
+                // Whatever state the pin ended up in, try to leave the device deselected.
+                let _ = cs.set_high();
+                return Err(Ads129xxError::Pin(e));
+            }
+            delay.delay_ns(timing.cs_setup_ns);
+            let res = f(bus).and_then(|()| bus.flush());
+            delay.delay_ns(timing.cs_hold_ns);
+            let deselect = cs.set_high();
+            res?; // Drop out of function with SPIError only after setting nCS.
+            deselect.map_err(Ads129xxError::Pin)
+        });
+        delay.delay_ns(timing.idle_ns);
+        res
+    }
+}
+
+impl<'a, S, CS, D> Interface for SharedSpiInterface<'a, S, CS, D>
+where
+    S: SharedBus,
+    S::Bus: SpiBus + SetMode,
+    CS: OutputPin,
+    D: DelayNs,
+{
+    type Error = <S::Bus as hal_spi::ErrorType>::Error;
+    type PinError = CS::Error;
+
+    #[inline]
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error, CS::Error> {
+        self.transaction(|bus| bus.transfer_in_place(buffer))
+    }
+
+    #[inline]
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error, CS::Error> {
+        self.transaction(|bus| bus.write(buffer))
+    }
+
+    #[inline]
+    fn wait(&mut self, i: u16) {
+        self.delay.delay_us(u32::from(i) * WAIT_PERIOD_US);
+    }
+
+    #[inline]
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    /// A bus that remembers its mode, and how often it was switched
+    struct FakeBus {
+        mode: hal_spi::Mode,
+        switches: usize,
+    }
+
+    impl hal_spi::ErrorType for FakeBus {
+        type Error = Infallible;
+    }
+
+    impl SetMode for FakeBus {
+        fn set_mode(&mut self, mode: hal_spi::Mode) -> core::result::Result<(), Infallible> {
+            if self.mode != mode {
+                self.mode = mode;
+                self.switches += 1;
+            }
+            Ok(())
+        }
+    }
+
+    impl SpiBus for FakeBus {
+        fn read(&mut self, _words: &mut [u8]) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn write(&mut self, _words: &[u8]) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn transfer(
+            &mut self,
+            _read: &mut [u8],
+            _write: &[u8],
+        ) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn transfer_in_place(&mut self, _words: &mut [u8]) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> core::result::Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    struct FakeCs<'a>(&'a Cell<bool>);
+
+    impl embedded_hal::digital::ErrorType for FakeCs<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for FakeCs<'_> {
+        fn set_low(&mut self) -> core::result::Result<(), Infallible> {
+            self.0.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> core::result::Result<(), Infallible> {
+            self.0.set(true);
+            Ok(())
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn shared_bus_switches_mode() {
+        let bus = RefCell::new(FakeBus {
+            mode: hal_spi::MODE_0,
+            switches: 0,
+        });
+        let cs = Cell::new(false);
+        let mut spi = SharedSpiInterface::new(&bus, FakeCs(&cs), NoDelay).unwrap();
+        assert!(cs.get());
+        assert_eq!(spi.timing(), Timing::INTERNAL_CLOCK);
+
+        spi.write(&[0x08]).unwrap();
+        spi.write(&[0x0A]).unwrap();
+        assert_eq!(bus.borrow().mode, MODE);
+        assert_eq!(bus.borrow().switches, 1);
+        assert!(cs.get());
+
+        // Another device switches the bus back in between
+        bus.borrow_mut().set_mode(hal_spi::MODE_0).unwrap();
+        spi.write(&[0x08]).unwrap();
+        assert_eq!(bus.borrow().mode, MODE);
+    }
+}
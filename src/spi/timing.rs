@@ -0,0 +1,110 @@
+use core::convert::TryFrom;
+
+/// nCS low to first SCLK setup time (tCSSC), in nanoseconds
+const CSSC_NS: u32 = 17;
+/// Last SCLK to nCS high (tSCCS), in device clock periods
+const SCCS_TCLK: u32 = 4;
+/// Command decode time (tSDECODE), in device clock periods
+const SDECODE_TCLK: u32 = 4;
+
+/// Delays around and in between SPI transactions.
+///
+/// The datasheet specifies these in periods of the device clock (tCLK), so they depend on
+/// whether the device runs from its internal 512 kHz oscillator or an external clock. Use
+/// `from_fclk` to get the minimal delays for a given clock. Keep SCLK at most twice fCLK, so
+/// that the bytes of multi-byte commands are spaced by tSDECODE as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// Delay after pulling nCS low, before the first SCLK
+    pub cs_setup_ns: u32,
+    /// Delay after the last SCLK, before releasing nCS
+    pub cs_hold_ns: u32,
+    /// Delay after releasing nCS, before the next transaction
+    pub idle_ns: u32,
+    /// Period of the device clock, for the delays around resets and mode changes
+    pub tclk_ns: u32,
+}
+
+impl Timing {
+    /// Generous delays that work for any supported clock; the default of the SPI interfaces.
+    pub const CONSERVATIVE: Timing = Timing {
+        cs_setup_ns: 40_000,
+        cs_hold_ns: 40_000,
+        idle_ns: 20_000,
+        tclk_ns: 2_000,
+    };
+
+    /// Minimal delays when running from the internal 512 kHz oscillator
+    pub const INTERNAL_CLOCK: Timing = Timing::from_fclk(512_000);
+
+    /// Minimal delays when running from an external 2.048 MHz clock
+    pub const EXTERNAL_CLOCK: Timing = Timing::from_fclk(2_048_000);
+
+    /// Minimal delays for a device clock of `fclk_hz`.
+    pub const fn from_fclk(fclk_hz: u32) -> Timing {
+        let tclk_ns = 1_000_000_000u32.div_ceil(fclk_hz);
+        Timing {
+            cs_setup_ns: CSSC_NS,
+            cs_hold_ns: SCCS_TCLK * tclk_ns,
+            idle_ns: SDECODE_TCLK * tclk_ns,
+            tclk_ns,
+        }
+    }
+
+    /// The delays in ticks of a timer running at `timer_hz`, rounded up.
+    pub fn ticks(&self, timer_hz: u32) -> TimerTicks {
+        let ticks = |ns: u32| {
+            let ticks = (u64::from(ns) * u64::from(timer_hz)).div_ceil(1_000_000_000);
+            u16::try_from(ticks).unwrap_or(u16::MAX)
+        };
+        TimerTicks {
+            cs_setup: ticks(self.cs_setup_ns),
+            cs_hold: ticks(self.cs_hold_ns),
+            idle: ticks(self.idle_ns),
+        }
+    }
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing::CONSERVATIVE
+    }
+}
+
+/// The delays of a `Timing`, in timer overflows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerTicks {
+    /// Delay after pulling nCS low
+    pub cs_setup: u16,
+    /// Delay before releasing nCS
+    pub cs_hold: u16,
+    /// Delay after releasing nCS
+    pub idle: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_from_fclk() {
+        assert_eq!(Timing::INTERNAL_CLOCK.cs_hold_ns, 4 * 1954);
+        assert_eq!(Timing::EXTERNAL_CLOCK.idle_ns, 4 * 489);
+        assert_eq!(Timing::INTERNAL_CLOCK.tclk_ns, 1954);
+
+        // The delays the embedded-hal 0.2 interface always used with a 500 kHz timer
+        let ticks = Timing::CONSERVATIVE.ticks(500_000);
+        assert_eq!(
+            ticks,
+            TimerTicks {
+                cs_setup: 20,
+                cs_hold: 20,
+                idle: 10
+            }
+        );
+
+        let ticks = Timing::INTERNAL_CLOCK.ticks(1_000_000);
+        assert_eq!(ticks.cs_setup, 1);
+        assert_eq!(ticks.cs_hold, 8);
+    }
+}
@@ -1,8 +1,8 @@
-use embedded_hal::timer::CountDown;
+use embedded_hal_02::timer::CountDown;
 use nb::block;
 
 /// Blockingly wait i clock overflows.
-pub fn wait<TIM: CountDown>(timer: &mut TIM, i: u16) {
+pub fn wait<TIM: CountDown>(timer: &mut TIM, i: u32) {
     for _ in 0..i {
         match block!(timer.wait()) {
             Ok(()) => (),